@@ -1,7 +1,9 @@
 use super::*;
 use crate::commit::CommitInfo;
+use chrono::SecondsFormat;
 use log::*;
 use schema::*;
+use std::convert::TryInto;
 use url::Url;
 use yew::callback::Callback;
 use yew::format::{Json, Nothing};
@@ -20,16 +22,52 @@ impl GitHubApi {
         }
     }
 
-    fn build_commits_url(repo: &str, from: Option<&str>, page: u32, count: u32) -> Url {
-        let page = page.to_string();
-        let count = count.to_string();
-        let mut params = vec![("page", page.as_ref()), ("per_page", count.as_ref())];
-        if let Some(sha) = from {
+    fn build_commits_url(req: &CommitListRequest) -> Url {
+        let page = req.page.to_string();
+        let count = COMMITS_PAGE_SIZE.to_string();
+        let since = req
+            .since
+            .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true));
+        let until = req
+            .until
+            .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true));
+        let mut params: Vec<(&str, &str)> = vec![("page", &page), ("per_page", &count)];
+        if let Some(sha) = req.from.as_ref() {
             params.push(("sha", sha));
         }
-        Url::parse_with_params(&format!("{}/repos/{}/commits", ENDPOINT, repo), &params)
+        if let Some(since) = since.as_ref() {
+            params.push(("since", since));
+        }
+        if let Some(until) = until.as_ref() {
+            params.push(("until", until));
+        }
+        Url::parse_with_params(&format!("{}/repos/{}/commits", ENDPOINT, req.repo), &params)
             .expect("error building commits API url")
     }
+
+    fn build_tags_url(req: &TagListRequest) -> Url {
+        Url::parse_with_params(
+            &format!("{}/repos/{}/tags", ENDPOINT, req.repo),
+            &[
+                ("page", req.page.to_string()),
+                ("per_page", TAGS_PAGE_SIZE.to_string()),
+            ],
+        )
+        .expect("error building tags API url")
+    }
+
+    fn build_compare_url(req: &CommitCompareRequest) -> Url {
+        let mut url = Url::parse(&format!("{}/repos/{}/compare/", ENDPOINT, req.repo))
+            .expect("error building compare API url");
+        url.path_segments_mut()
+            .expect("cannot-be-a-base endpoint")
+            .pop()
+            .push(&format!("{}...{}", req.base, req.head));
+        url.query_pairs_mut()
+            .append_pair("page", &req.page.to_string())
+            .append_pair("per_page", &COMMITS_PAGE_SIZE.to_string());
+        url
+    }
 }
 
 impl Api<CommitListRequest, Vec<CommitInfo>> for GitHubApi {
@@ -41,12 +79,7 @@ impl Api<CommitListRequest, Vec<CommitInfo>> for GitHubApi {
     where
         F: FnOnce(Result<Vec<CommitInfo>, Error>) + 'static,
     {
-        let url = Self::build_commits_url(
-            req.repo.as_ref(),
-            req.from.as_ref().map(|s| s.as_ref()),
-            req.page,
-            req.count,
-        );
+        let url = Self::build_commits_url(req);
         let request = Request::get(url.as_str())
             .body(Nothing)
             .expect("build request error");
@@ -59,6 +92,87 @@ impl Api<CommitListRequest, Vec<CommitInfo>> for GitHubApi {
                         "Received response for commit list: meta={:?}, data={:?}",
                         meta, data
                     );
+                    if meta.status.is_success() {
+                        match data {
+                            Ok(d) => callback(d.into_iter().map(TryInto::try_into).collect()),
+                            Err(e) => callback(Err(Error::Fetch(e.to_string()))),
+                        }
+                    } else {
+                        callback(Err(Error::Http(meta.status)));
+                    }
+                },
+            ),
+        )?))
+    }
+}
+
+impl Api<CommitCompareRequest, ComparePage> for GitHubApi {
+    fn call<F>(
+        &mut self,
+        req: &CommitCompareRequest,
+        callback: F,
+    ) -> Result<Option<FetchTask>, anyhow::Error>
+    where
+        F: FnOnce(Result<ComparePage, Error>) + 'static,
+    {
+        let url = Self::build_compare_url(req);
+        let request = Request::get(url.as_str())
+            .body(Nothing)
+            .expect("build request error");
+        Ok(Some(self.service.fetch(
+            request,
+            Callback::once(
+                move |resp: Response<Json<Result<CompareData, anyhow::Error>>>| {
+                    let (meta, Json(data)) = resp.into_parts();
+                    debug!(
+                        "Received response for commit compare: meta={:?}, data={:?}",
+                        meta, data
+                    );
+                    if meta.status.is_success() {
+                        match data {
+                            Ok(d) => {
+                                let total_commits = d.total_commits;
+                                let commits = d.commits.into_iter().map(TryInto::try_into);
+                                callback(commits.collect::<Result<_, _>>().map(|commits| {
+                                    ComparePage {
+                                        total_commits,
+                                        commits,
+                                    }
+                                }))
+                            }
+                            Err(e) => callback(Err(Error::Fetch(e.to_string()))),
+                        }
+                    } else {
+                        callback(Err(Error::Http(meta.status)));
+                    }
+                },
+            ),
+        )?))
+    }
+}
+
+impl Api<TagListRequest, Vec<Tag>> for GitHubApi {
+    fn call<F>(
+        &mut self,
+        req: &TagListRequest,
+        callback: F,
+    ) -> Result<Option<FetchTask>, anyhow::Error>
+    where
+        F: FnOnce(Result<Vec<Tag>, Error>) + 'static,
+    {
+        let url = Self::build_tags_url(req);
+        let request = Request::get(url.as_str())
+            .body(Nothing)
+            .expect("build request error");
+        Ok(Some(self.service.fetch(
+            request,
+            Callback::once(
+                move |resp: Response<Json<Result<Vec<TagData>, anyhow::Error>>>| {
+                    let (meta, Json(data)) = resp.into_parts();
+                    debug!(
+                        "Received response for tags: meta={:?}, data={:?}",
+                        meta, data
+                    );
                     if meta.status.is_success() {
                         match data {
                             Ok(d) => callback(Ok(d.into_iter().map(Into::into).collect())),
@@ -74,15 +188,31 @@ impl Api<CommitListRequest, Vec<CommitInfo>> for GitHubApi {
 }
 
 pub(super) mod schema {
+    use crate::api;
     use crate::commit;
     use chrono::DateTime;
     use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
+    use std::time::SystemTime;
 
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct CommitData {
         pub sha: String,
         pub commit: Commit,
         pub html_url: String,
+        #[serde(default)]
+        pub parents: Vec<Parent>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Parent {
+        pub sha: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct CompareData {
+        pub total_commits: u32,
+        pub commits: Vec<CommitData>,
     }
 
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -99,8 +229,36 @@ pub(super) mod schema {
         pub date: String,
     }
 
-    impl From<CommitData> for commit::CommitInfo {
-        fn from(data: CommitData) -> Self {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct TagData {
+        pub name: String,
+        pub commit: Parent,
+    }
+
+    impl From<TagData> for api::Tag {
+        fn from(data: TagData) -> Self {
+            Self {
+                name: data.name,
+                commit: data.commit.sha,
+            }
+        }
+    }
+
+    fn parse_date(date: &str, field: &str, sha: &str) -> Result<SystemTime, api::Error> {
+        DateTime::parse_from_rfc3339(date)
+            .map(Into::into)
+            .map_err(|e| {
+                api::Error::InvalidResponse(format!(
+                    "malformed {} '{}' of commit {}: {}",
+                    field, date, sha, e
+                ))
+            })
+    }
+
+    impl TryFrom<CommitData> for commit::CommitInfo {
+        type Error = api::Error;
+
+        fn try_from(data: CommitData) -> Result<Self, Self::Error> {
             let author = commit::UserInfo {
                 name: data.commit.author.name,
                 email: data.commit.author.email,
@@ -110,19 +268,16 @@ pub(super) mod schema {
                 email: data.commit.committer.email,
             };
 
-            Self {
+            Ok(Self {
+                author_date: parse_date(&data.commit.author.date, "author.date", &data.sha)?,
+                commit_date: parse_date(&data.commit.committer.date, "committer.date", &data.sha)?,
                 sha: data.sha,
                 author,
-                author_date: DateTime::parse_from_rfc3339(&data.commit.author.date)
-                    .expect("parse author.date")
-                    .into(),
                 committer,
-                commit_date: DateTime::parse_from_rfc3339(&data.commit.author.date)
-                    .expect("parse committer.date")
-                    .into(),
                 message: data.commit.message,
                 view_url: data.html_url,
-            }
+                parents: data.parents.into_iter().map(|p| p.sha).collect(),
+            })
         }
     }
 }
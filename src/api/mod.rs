@@ -1,8 +1,15 @@
 pub mod github;
 pub mod static_metadata;
 
+use crate::commit::CommitInfo;
+use chrono::{DateTime, Utc};
+use github::GitHubApi;
 use http::status::StatusCode;
+use serde::{Deserialize, Serialize};
+use static_metadata::StaticMetadataApi;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::rc::Rc;
 use thiserror::Error;
 use yew::services::fetch::FetchTask;
 
@@ -12,6 +19,24 @@ pub enum Error {
     Fetch(String),
     #[error("http error: {0}")]
     Http(StatusCode),
+    #[error("unexpected response: {0}")]
+    InvalidResponse(String),
+}
+
+impl Error {
+    /// Returns true if the same request might succeed when retried, e.g. on network failures,
+    /// server errors or rate limiting.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Fetch(_) => true,
+            Error::Http(status) => {
+                status.is_server_error()
+                    || *status == StatusCode::FORBIDDEN
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            Error::InvalidResponse(_) => false,
+        }
+    }
 }
 
 pub trait Api<Req: Debug, Res> {
@@ -20,12 +45,38 @@ pub trait Api<Req: Debug, Res> {
         F: FnOnce(Result<Res, Error>) + 'static;
 }
 
+/// Number of commits in a page of commits listing. It's fixed regardless of the size of ranges so
+/// that cached pages can be reused to assemble any range.
+pub const COMMITS_PAGE_SIZE: u32 = 100;
+
+/// Request to list a page of commits reachable from `from`, newest first. `since` is given only
+/// for date-windowed ranges, so that pages of count-bounded ranges are shared among those ending
+/// at the same `until`.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CommitListRequest {
     pub repo: String,
     pub from: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub page: u32,
+}
+
+/// Request to list a page of commits which are reachable from `head` but not from `base`, oldest
+/// first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommitCompareRequest {
+    pub repo: String,
+    pub base: String,
+    pub head: String,
     pub page: u32,
-    pub count: u32,
+}
+
+/// A page of commits between two refs in chronological order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparePage {
+    /// Number of commits between the refs in all pages.
+    pub total_commits: u32,
+    pub commits: Vec<CommitInfo>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -33,3 +84,79 @@ pub struct CommitMetadataRequest {
     pub commit: String,
     pub file: String,
 }
+
+/// Request to get the manifest of commits which have metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ManifestRequest;
+
+/// Set of commits having metadata for each file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest(Rc<HashMap<String, HashSet<String>>>);
+
+impl Manifest {
+    pub fn new(files: HashMap<String, HashSet<String>>) -> Self {
+        Self(Rc::new(files))
+    }
+
+    pub fn contains(&self, file: &str, commit: &str) -> bool {
+        self.0
+            .get(file)
+            .map(|commits| commits.contains(commit))
+            .unwrap_or(false)
+    }
+}
+
+/// Number of tags in a page of tags listing.
+pub const TAGS_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagListRequest {
+    pub repo: String,
+    /// Page of tags counting from 1. Static tags are all listed in the first page.
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    /// Sha of the commit which the tag points to.
+    pub commit: String,
+}
+
+impl Tag {
+    /// Group names of tags by commit they point to.
+    pub fn by_commit(tags: Vec<Tag>) -> HashMap<String, Vec<String>> {
+        let mut by_commit: HashMap<_, Vec<_>> = HashMap::new();
+        for tag in tags {
+            by_commit.entry(tag.commit).or_default().push(tag.name);
+        }
+        by_commit
+    }
+}
+
+/// Lists tags from the source chosen by configuration.
+pub enum TagsApi {
+    GitHub(GitHubApi),
+    Static(StaticMetadataApi),
+    Disabled,
+}
+
+impl Api<TagListRequest, Vec<Tag>> for TagsApi {
+    fn call<F>(
+        &mut self,
+        req: &TagListRequest,
+        callback: F,
+    ) -> Result<Option<FetchTask>, anyhow::Error>
+    where
+        F: FnOnce(Result<Vec<Tag>, Error>) + 'static,
+    {
+        match self {
+            TagsApi::GitHub(api) => api.call(req, callback),
+            TagsApi::Static(api) => api.call(req, callback),
+            TagsApi::Disabled => {
+                callback(Ok(Vec::new()));
+                Ok(None)
+            }
+        }
+    }
+}
@@ -1,9 +1,21 @@
 use super::*;
+use crate::query;
+use jmespatch::Rcvar;
 use log::*;
+use std::collections::{HashMap, HashSet};
 use yew::callback::Callback;
-use yew::format::Nothing;
+use yew::format::{Json, Nothing};
 use yew::services::fetch::{FetchService, FetchTask, Request, Response};
 
+/// Name of the file placed at the top of `data_url`, containing JSON object which maps each file
+/// name to the list of commits having the file.
+const MANIFEST_FILE: &str = "manifest.json";
+/// Name of the file placed at the top of `data_url`, containing JSON array of tags in the form of
+/// `{"name": "v1.0.0", "commit": "<sha>"}`.
+const TAGS_FILE: &str = "tags.json";
+
+type ManifestData = HashMap<String, HashSet<String>>;
+
 pub struct StaticMetadataApi {
     service: FetchService,
     data_url: String,
@@ -20,16 +32,26 @@ impl StaticMetadataApi {
     fn build_url(&self, commit: &str, file: &str) -> String {
         format!("{}/{}/{}", self.data_url, commit, file)
     }
+
+    fn build_manifest_url(&self) -> String {
+        format!("{}/{}", self.data_url, MANIFEST_FILE)
+    }
+
+    fn build_tags_url(&self) -> String {
+        format!("{}/{}", self.data_url, TAGS_FILE)
+    }
 }
 
-impl Api<CommitMetadataRequest, String> for StaticMetadataApi {
+/// Metadata are parsed as they arrive, so that cached responses are queried without parsing them
+/// again. Malformed JSON is reported as `Error::InvalidResponse`.
+impl Api<CommitMetadataRequest, Rcvar> for StaticMetadataApi {
     fn call<F>(
         &mut self,
         req: &CommitMetadataRequest,
         callback: F,
     ) -> Result<Option<FetchTask>, anyhow::Error>
     where
-        F: FnOnce(Result<String, Error>) + 'static,
+        F: FnOnce(Result<Rcvar, Error>) + 'static,
     {
         let url = self.build_url(req.commit.as_ref(), req.file.as_ref());
         let request = Request::get(&url)
@@ -45,7 +67,10 @@ impl Api<CommitMetadataRequest, String> for StaticMetadataApi {
                 );
                 if meta.status.is_success() {
                     match data {
-                        Ok(d) => callback(Ok(d)),
+                        Ok(d) => callback(
+                            query::parse_json(&d)
+                                .map_err(|e| Error::InvalidResponse(e.to_string())),
+                        ),
                         Err(e) => callback(Err(Error::Fetch(e.to_string()))),
                     }
                 } else {
@@ -55,3 +80,76 @@ impl Api<CommitMetadataRequest, String> for StaticMetadataApi {
         )?))
     }
 }
+
+impl Api<ManifestRequest, Manifest> for StaticMetadataApi {
+    fn call<F>(
+        &mut self,
+        _req: &ManifestRequest,
+        callback: F,
+    ) -> Result<Option<FetchTask>, anyhow::Error>
+    where
+        F: FnOnce(Result<Manifest, Error>) + 'static,
+    {
+        let url = self.build_manifest_url();
+        let request = Request::get(&url)
+            .body(Nothing)
+            .expect("build request error");
+        Ok(Some(self.service.fetch(
+            request,
+            Callback::once(
+                move |resp: Response<Json<Result<ManifestData, anyhow::Error>>>| {
+                    let (meta, Json(data)) = resp.into_parts();
+                    debug!("Received response for manifest: meta={:?}", meta);
+                    if meta.status.is_success() {
+                        match data {
+                            Ok(d) => callback(Ok(Manifest::new(d))),
+                            Err(e) => callback(Err(Error::Fetch(e.to_string()))),
+                        }
+                    } else {
+                        callback(Err(Error::Http(meta.status)));
+                    }
+                },
+            ),
+        )?))
+    }
+}
+
+impl Api<TagListRequest, Vec<Tag>> for StaticMetadataApi {
+    fn call<F>(
+        &mut self,
+        req: &TagListRequest,
+        callback: F,
+    ) -> Result<Option<FetchTask>, anyhow::Error>
+    where
+        F: FnOnce(Result<Vec<Tag>, Error>) + 'static,
+    {
+        if req.page > 1 {
+            callback(Ok(Vec::new()));
+            return Ok(None);
+        }
+        let url = self.build_tags_url();
+        let request = Request::get(&url)
+            .body(Nothing)
+            .expect("build request error");
+        Ok(Some(self.service.fetch(
+            request,
+            Callback::once(
+                move |resp: Response<Json<Result<Vec<Tag>, anyhow::Error>>>| {
+                    let (meta, Json(data)) = resp.into_parts();
+                    debug!(
+                        "Received response for tags: meta={:?}, data={:?}",
+                        meta, data
+                    );
+                    if meta.status.is_success() {
+                        match data {
+                            Ok(d) => callback(Ok(d)),
+                            Err(e) => callback(Err(Error::Fetch(e.to_string()))),
+                        }
+                    } else {
+                        callback(Err(Error::Http(meta.status)));
+                    }
+                },
+            ),
+        )?))
+    }
+}
@@ -51,32 +51,42 @@ where
         }
         debug!("API cache miss: {:?}", key);
 
+        // Register the callback before calling the API as it might complete synchronously.
+        self.cache.borrow_mut().insert(
+            key.clone(),
+            RequestState::InFlight(None, vec![Box::new(callback)]),
+        );
         let cache = Rc::clone(&self.cache);
         let key_cp = key.clone();
-        let task = self.api.call(
+        let ret = self.api.call(
             key,
             Box::new(move |resp: Result<R, api::Error>| {
-                if let Some(RequestState::InFlight(_, callbacks)) =
-                    cache.borrow_mut().remove(&key_cp)
-                {
+                let state = cache.borrow_mut().remove(&key_cp);
+                // Transient errors aren't cached so that the request is retried next time, whereas
+                // definitive ones like missing metadata files are not requested again.
+                if !matches!(&resp, Err(e) if e.is_transient()) {
+                    cache
+                        .borrow_mut()
+                        .insert(key_cp, RequestState::Cached(resp.clone()));
+                }
+                if let Some(RequestState::InFlight(_, callbacks)) = state {
                     for cb in callbacks {
                         cb(resp.clone());
                     }
                 }
-                cache
-                    .borrow_mut()
-                    .insert(key_cp, RequestState::Cached(resp));
             }),
-        )?;
-        if let Some(task) = task {
-            let mut cache = self.cache.borrow_mut();
-            // The above API call might completes synchronously and the response already
-            // resides in cache.
-            if !cache.contains_key(&key) {
-                cache.insert(
-                    key.clone(),
-                    RequestState::InFlight(task, vec![Box::new(callback)]),
-                );
+        );
+        match ret {
+            Ok(Some(task)) => {
+                if let Some(RequestState::InFlight(held, _)) = self.cache.borrow_mut().get_mut(key)
+                {
+                    held.replace(task);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                self.cache.borrow_mut().remove(key);
+                return Err(e);
             }
         }
         Ok(())
@@ -99,6 +109,8 @@ where
 }
 
 pub enum RequestState<R> {
-    InFlight(FetchTask, Vec<Box<dyn FnOnce(R) + 'static>>),
+    // The task is held only to keep the request alive.
+    #[allow(dead_code)]
+    InFlight(Option<FetchTask>, Vec<Box<dyn FnOnce(R) + 'static>>),
     Cached(R),
 }
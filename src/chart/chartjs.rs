@@ -1,14 +1,30 @@
-use crate::chart;
+use crate::chart::{
+    self, BORDER_COLOR, OVERLAY_COLORS, SERIES_COLORS, TAG_LINE_COLOR, THRESHOLD_COLOR,
+};
+use crate::commit::CommitInfo;
 use crate::component::CommitViewData;
-use js_sys::{Array, Object, Reflect};
+use crate::config::{Axis, AxisPosition, ChartType, Scale, Threshold, XAxis};
+use crate::format::{self, ValueFormatter};
+use crate::overlay;
+use js_sys::{Array, Function, Object, Reflect};
 use log::*;
-use number_prefix::NumberPrefix;
+use std::any::Any;
+use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
-use web_sys::Element;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, Element};
 
-const BORDER_COLOR: &str = "rgba(1,169,244,0.5)";
 const BACKGROUND_COLOR: &str = "rgba(1,169,244,0.2)";
+const POINT_STYLE: &str = "circle";
+const MERGE_POINT_STYLE: &str = "rectRot";
+const POINT_RADIUS: f64 = 3.0;
+const MERGE_POINT_RADIUS: f64 = 5.0;
+const TAG_LABEL_FONT: &str = "10px sans-serif";
+const Y_AXIS_ID: &str = "y-axis-0";
+const RIGHT_Y_AXIS_ID: &str = "y-axis-1";
+const CANVAS_WIDTH: &str = "400";
+const CANVAS_HEIGHT: &str = "200";
 
 #[wasm_bindgen(module = "chart.js")]
 extern "C" {
@@ -20,124 +36,595 @@ extern "C" {
     #[wasm_bindgen(method)]
     pub fn destroy(this: &Chart);
 
+    #[wasm_bindgen(method)]
+    pub fn update(this: &Chart);
+
+    #[wasm_bindgen(method, js_name = getDatasetMeta)]
+    pub fn get_dataset_meta(this: &Chart, index: u32) -> JsValue;
 }
 
 pub struct ChartJs {
     chart: Chart,
-    _closures: Vec<Box<dyn Drop>>,
+    canvas: Element,
+    config: chart::Config,
+    state: Rc<RefCell<ChartData>>,
+    _closures: Vec<Box<dyn Any>>,
 }
 
 impl ChartJs {
-    fn format_value(val: f64) -> String {
-        match NumberPrefix::decimal(val) {
-            NumberPrefix::Standalone(n) => n.to_string(),
-            NumberPrefix::Prefixed(prefix, n) => format!("{}{}", n, prefix),
+    /// Build array of datapoints, which are `{x, y}` objects on time scale or bare values.
+    fn data_array(times: Option<&[f64]>, values: &[Option<f64>]) -> Array {
+        match times {
+            Some(times) => times
+                .iter()
+                .zip(values)
+                .map(|(x, y)| JsValue::from(js_obj! { x => js_ref!(*x), y => js_ref!(*y) }))
+                .collect(),
+            None => values.iter().copied().map(JsValue::from).collect(),
+        }
+    }
+
+    /// Create the callback to format tick labels of an Y axis.
+    /// Returns the JS function formatting ticks, whose closure is kept alive by `closures`.
+    fn ticks_callback(
+        closures: &mut Vec<Box<dyn Any>>,
+        scale: Scale,
+        formatter: ValueFormatter,
+    ) -> JsValue {
+        closure!(
+            closures,
+            dyn Fn(JsValue, JsValue, JsValue) -> JsValue,
+            move |value: JsValue, _, _| {
+                let value = match value.as_f64() {
+                    Some(value) => value,
+                    None => return value,
+                };
+                if scale == Scale::Logarithmic && !is_log_major_tick(value) {
+                    // Log scale generates ticks at every integer multiple of powers of 10,
+                    // which overlap if all labeled.
+                    return JsValue::from_str("");
+                }
+                JsValue::from_str(&formatter.format(value))
+            }
+        )
+        .as_ref()
+        .clone()
+    }
+
+    fn y_axis(
+        id: &str,
+        position: &str,
+        axis: &Axis,
+        ticks_cb: &JsValue,
+        suggested_min: Option<f64>,
+        suggested_max: Option<f64>,
+    ) -> Object {
+        js_obj! {
+            id => js_ref!(id),
+            position => js_ref!(position),
+            type => js_ref!(match axis.scale {
+                Scale::Linear => "linear",
+                Scale::Logarithmic => "logarithmic",
+            }),
+            scaleLabel => js_obj! {
+                display => js_ref!(axis.title.is_some()),
+                labelString => js_ref!(axis.title.as_deref().unwrap_or("")),
+            }.as_ref(),
+            ticks => js_obj! {
+                beginAtZero => js_ref!(axis.begin_at_zero),
+                min => js_ref!(axis.min),
+                max => js_ref!(axis.max),
+                suggestedMin => js_ref!(suggested_min),
+                suggestedMax => js_ref!(suggested_max),
+                callback => ticks_cb,
+            }.as_ref(),
         }
     }
+
+    /// Set options to draw a dataset of values as the configured type of chart.
+    fn set_chart_type(dataset: &Object, config: &chart::Config) {
+        let set = |key: &str, value: JsValue| {
+            Reflect::set(dataset, &JsValue::from_str(key), &value)
+                .expect("error setting js attribute of dataset");
+        };
+        set("spanGaps", JsValue::from(config.span_gaps));
+        match config.chart_type {
+            ChartType::Line | ChartType::Bar => {}
+            ChartType::Step => set("steppedLine", JsValue::TRUE),
+            ChartType::Scatter => set("showLine", JsValue::FALSE),
+        }
+    }
+
+    fn get_f64(obj: &JsValue, key: &str) -> Option<f64> {
+        Reflect::get(obj, &JsValue::from_str(key)).ok()?.as_f64()
+    }
+
+    fn y_pixel(chart: &Chart, value: f64) -> Option<f64> {
+        let scales = Reflect::get(chart, &JsValue::from_str("scales")).ok()?;
+        let scale = Reflect::get(&scales, &JsValue::from_str(Y_AXIS_ID)).ok()?;
+        let get_pixel = Reflect::get(&scale, &JsValue::from_str("getPixelForValue"))
+            .ok()?
+            .dyn_into::<Function>()
+            .ok()?;
+        get_pixel
+            .call1(&scale, &JsValue::from(value))
+            .ok()?
+            .as_f64()
+    }
+
+    /// Draw vertical lines at the X position of each tagged datapoint and horizontal lines at
+    /// each threshold, with labels. Called from chart plugin's `afterDraw` hook.
+    fn draw_annotations(chart: &Chart, tags: &[(usize, String)], thresholds: &[Threshold]) {
+        let ctx = match Reflect::get(chart, &JsValue::from_str("ctx"))
+            .ok()
+            .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+        {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let area = Reflect::get(chart, &JsValue::from_str("chartArea")).unwrap_or_default();
+        let (top, bottom, left, right) = match (
+            Self::get_f64(&area, "top"),
+            Self::get_f64(&area, "bottom"),
+            Self::get_f64(&area, "left"),
+            Self::get_f64(&area, "right"),
+        ) {
+            (Some(top), Some(bottom), Some(left), Some(right)) => (top, bottom, left, right),
+            _ => return,
+        };
+        let points = Reflect::get(&chart.get_dataset_meta(0), &JsValue::from_str("data"))
+            .map(|data| Array::from(&data))
+            .unwrap_or_else(|_| Array::new());
+
+        ctx.save();
+        ctx.set_font(TAG_LABEL_FONT);
+        ctx.set_line_width(1.0);
+        let _ = ctx.set_line_dash(js_arr![js_ref!(4), js_ref!(4)].as_ref());
+        ctx.set_stroke_style_str(TAG_LINE_COLOR);
+        ctx.set_fill_style_str(TAG_LINE_COLOR);
+        for (index, label) in tags {
+            let model = Reflect::get(&points.get(*index as u32), &JsValue::from_str("_model"))
+                .unwrap_or_default();
+            let x = match Self::get_f64(&model, "x") {
+                Some(x) => x,
+                None => continue,
+            };
+            ctx.begin_path();
+            ctx.move_to(x, top);
+            ctx.line_to(x, bottom);
+            ctx.stroke();
+            let _ = ctx.fill_text(label, x + 2.0, top + 10.0);
+        }
+        for threshold in thresholds {
+            let y = match Self::y_pixel(chart, threshold.value) {
+                Some(y) if y >= top && y <= bottom => y,
+                _ => continue,
+            };
+            let color = threshold.color.as_deref().unwrap_or(THRESHOLD_COLOR);
+            ctx.set_stroke_style_str(color);
+            ctx.set_fill_style_str(color);
+            ctx.begin_path();
+            ctx.move_to(left, y);
+            ctx.line_to(right, y);
+            ctx.stroke();
+            let _ = ctx.fill_text(&threshold.label(), left + 2.0, y - 2.0);
+        }
+        ctx.restore();
+    }
 }
 
-impl chart::Chart for ChartJs {
-    fn create(target: Element, config: &chart::Config, data: &CommitViewData) -> Self {
-        let commits = Rc::new(
-            data.commits
-                .iter()
-                .map(Clone::clone)
-                .rev()
-                .collect::<Vec<_>>(),
-        );
+/// Part of the chart which changes by update, shared with callbacks given to Chart.js.
+struct ChartData {
+    /// Commits in chronological order, which is the order of datapoints.
+    commits: Vec<CommitInfo>,
+    /// View data the chart is showing, to describe datapoints and pass them to hooks.
+    view: Rc<CommitViewData>,
+    /// Index of datapoints having tags, with their labels.
+    tags: Vec<(usize, String)>,
+    /// Index of the datapoint hovered last, not to call the hook repeatedly while on it.
+    hovered: Option<usize>,
+}
+
+impl ChartData {
+    fn new(data: &Rc<CommitViewData>) -> Self {
+        let commits: Vec<_> = data.commits.iter().rev().cloned().collect();
+        let tags = commits
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| data.tags.get(&c.sha).map(|names| (i, names.join(", "))))
+            .collect();
+        Self {
+            commits,
+            view: Rc::clone(data),
+            tags,
+            hovered: None,
+        }
+    }
+}
+
+impl ChartJs {
+    /// Build `data` of Chart.js config, which has labels and datasets of the chart.
+    fn build_data(config: &chart::Config, data: &CommitViewData, commits: &[CommitInfo]) -> Object {
         let mut labels = Vec::with_capacity(commits.len());
         let mut datapoints = Vec::with_capacity(commits.len());
-        for commit in commits.iter() {
+        let mut point_styles = Vec::with_capacity(commits.len());
+        let mut point_radiuses = Vec::with_capacity(commits.len());
+        let mut point_colors = Vec::with_capacity(commits.len());
+        for commit in commits {
             labels.push(commit.sha_short());
-            let value = data.metadata.get(&commit.sha);
-            datapoints.push(value.copied());
+            let value = data.metadata.get(&commit.sha).copied();
+            datapoints.push(value);
+            point_colors.push(config.point_color(value));
+            if commit.is_merge() {
+                point_styles.push(MERGE_POINT_STYLE);
+                point_radiuses.push(MERGE_POINT_RADIUS);
+            } else {
+                point_styles.push(POINT_STYLE);
+                point_radiuses.push(POINT_RADIUS);
+            }
         }
 
-        let mut closures: Vec<Box<dyn Drop>> = Vec::new();
+        let times = if config.x_axis == XAxis::Commit {
+            None
+        } else {
+            Some(
+                commits
+                    .iter()
+                    .map(|c| c.timestamp_millis(config.x_axis).unwrap_or(f64::NAN))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let xs = times
+            .clone()
+            .unwrap_or_else(|| (0..commits.len()).map(|i| i as f64).collect());
 
-        let coms = Rc::clone(&commits);
+        let point_colors = point_colors
+            .into_iter()
+            .map(JsValue::from)
+            .collect::<Array>();
+        let main_dataset = js_obj! {
+            backgroundColor => js_ref!(BACKGROUND_COLOR),
+            borderColor => js_ref!(BORDER_COLOR),
+            label => js_ref!(&config.title),
+            yAxisID => js_ref!(Y_AXIS_ID),
+            data => &Self::data_array(times.as_deref(), &datapoints),
+            pointStyle => &point_styles.into_iter().map(JsValue::from).collect::<Array>(),
+            pointRadius => &point_radiuses.into_iter().map(JsValue::from).collect::<Array>(),
+            pointBorderColor => &point_colors,
+            pointBackgroundColor => &point_colors,
+        };
+        Self::set_chart_type(&main_dataset, config);
+        if config.chart_type == ChartType::Bar {
+            // Bars have no points, so threshold violations are marked by the color of bars.
+            Reflect::set(
+                &main_dataset,
+                &JsValue::from_str("backgroundColor"),
+                &point_colors,
+            )
+            .expect("error setting js attribute: backgroundColor");
+        }
+        let datasets = js_arr![main_dataset.as_ref()];
+        for (i, series) in config.series.iter().enumerate() {
+            let values: Vec<_> = commits
+                .iter()
+                .map(|c| data.series.get(i).and_then(|v| v.get(&c.sha)).copied())
+                .collect();
+            let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+            let dataset = js_obj! {
+                borderColor => js_ref!(color),
+                backgroundColor => js_ref!(color),
+                fill => js_ref!(false),
+                label => js_ref!(&series.title),
+                yAxisID => js_ref!(match series.y_axis {
+                    AxisPosition::Left => Y_AXIS_ID,
+                    AxisPosition::Right => RIGHT_Y_AXIS_ID,
+                }),
+                data => &Self::data_array(times.as_deref(), &values),
+            };
+            Self::set_chart_type(&dataset, config);
+            datasets.push(dataset.as_ref());
+        }
+        for (i, ov) in config.overlays.iter().enumerate() {
+            let values = overlay::compute(ov, &xs, &datapoints);
+            datasets.push(
+                js_obj! {
+                    // Overlays are always lines, even on bar charts.
+                    type => js_ref!("line"),
+                    borderColor => js_ref!(OVERLAY_COLORS[i % OVERLAY_COLORS.len()]),
+                    borderWidth => js_ref!(1.5),
+                    fill => js_ref!(false),
+                    pointRadius => js_ref!(0),
+                    spanGaps => js_ref!(true),
+                    label => js_ref!(&ov.label()),
+                    yAxisID => js_ref!(Y_AXIS_ID),
+                    data => &Self::data_array(times.as_deref(), &values),
+                }
+                .as_ref(),
+            );
+        }
+
+        // Time scale takes X values from datapoints, and would try to parse labels as time.
+        let labels = if times.is_some() {
+            Array::new()
+        } else {
+            labels.into_iter().map(JsValue::from).collect::<Array>()
+        };
+        js_obj! {
+            labels => &labels,
+            datasets => datasets.as_ref(),
+        }
+    }
+}
+
+impl chart::Chart for ChartJs {
+    fn create(target: Element, config: &chart::Config, data: &Rc<CommitViewData>) -> Self {
+        let state = Rc::new(RefCell::new(ChartData::new(data)));
+        let chart_data = Self::build_data(config, data, &state.borrow().commits);
+
+        let mut closures: Vec<Box<dyn Any>> = Vec::new();
+
+        let click_state = Rc::clone(&state);
+        let click_config = config.clone();
         let on_click = closure!(
             closures,
             dyn Fn(JsValue, Array),
             move |_: JsValue, elems: Array| {
-                let index = Reflect::get(&elems.get(0), &JsValue::from_str("_index"))
-                    .expect("no elems[0]._index")
-                    .as_f64()
-                    .unwrap() as usize;
-                let url = &coms[index].view_url;
-                debug!("ChartJs onClick: index={}, link={}", index, url);
-                yew::utils::window()
-                    .open_with_url_and_target(url, "_blank")
-                    .expect("window.open");
+                // Clicks outside of datapoints come with no elements.
+                let index = match Self::get_f64(&elems.get(0), "_index") {
+                    Some(index) => index as usize,
+                    None => return,
+                };
+                debug!("ChartJs onClick: index={}", index);
+                let (point, url) = {
+                    let state = click_state.borrow();
+                    match state.commits.get(index) {
+                        Some(commit) => (
+                            chart::hook_point(&click_config, &state.view, commit),
+                            commit.view_url.clone(),
+                        ),
+                        None => return,
+                    }
+                };
+                chart::click_point(&click_config, &point, &url);
+            }
+        );
+
+        let hover_state = Rc::clone(&state);
+        let hover_config = config.clone();
+        let on_hover = closure!(
+            closures,
+            dyn Fn(JsValue, Array),
+            move |_: JsValue, elems: Array| {
+                let index = Self::get_f64(&elems.get(0), "_index").map(|index| index as usize);
+                let point = {
+                    let mut state = hover_state.borrow_mut();
+                    if state.hovered == index || hover_config.hooks.point_hover.is_none() {
+                        return;
+                    }
+                    state.hovered = index;
+                    match index.and_then(|index| state.commits.get(index)) {
+                        Some(commit) => chart::hook_point(&hover_config, &state.view, commit),
+                        None => return,
+                    }
+                };
+                hover_config.hooks.point_hover(&point);
             }
         );
 
-        let coms = Rc::clone(&commits);
+        let title_state = Rc::clone(&state);
+        let dates = config.dates.clone();
         let title_cb = closure!(
             closures,
             dyn Fn(Array, JsValue) -> JsValue,
             move |tl_item: Array, _data: JsValue| {
-                let t = Reflect::get(&tl_item.get(0), &JsValue::from_str("xLabel"))
-                    .unwrap()
-                    .as_string()
-                    .unwrap();
-                let index = Reflect::get(&tl_item.get(0), &JsValue::from_str("index"))
-                    .expect("no elems[0]._index")
-                    .as_f64()
-                    .unwrap() as usize;
-                let date = &coms[index].author_date_str();
-                let message = &coms[index].message_headline();
-                JsValue::from_str(&format!("{}\n{} {}", date, t, message))
+                let state = title_state.borrow();
+                let commit = match Self::get_f64(&tl_item.get(0), "index")
+                    .and_then(|index| state.commits.get(index as usize))
+                {
+                    Some(commit) => commit,
+                    None => return JsValue::from_str(""),
+                };
+                let title = chart::describe_commit(
+                    commit,
+                    state.view.spans.get(&commit.sha),
+                    state.view.statuses.get(&commit.sha),
+                    &dates,
+                );
+                JsValue::from_str(&title)
             }
         );
-        let yaxis_cb = closure!(
+        let formatters = format::series_formatters(
+            config.unit.as_ref(),
+            &config.axis,
+            &config.series,
+            &config.right_axis,
+        );
+        let right_formatter = config
+            .series
+            .iter()
+            .zip(&formatters[1..])
+            .find(|(s, _)| s.y_axis == AxisPosition::Right)
+            .map(|(_, f)| f.clone());
+        let left_ticks_cb =
+            Self::ticks_callback(&mut closures, config.axis.scale, formatters[0].clone());
+        let right_ticks_cb = right_formatter.as_ref().map(|formatter| {
+            Self::ticks_callback(&mut closures, config.right_axis.scale, formatter.clone())
+        });
+        // Formatters of each dataset, which are the main value, extra series then overlays.
+        let mut dataset_formatters = formatters.clone();
+        dataset_formatters.extend(config.overlays.iter().map(|_| formatters[0].clone()));
+        let label_cb = closure!(
             closures,
-            dyn Fn(JsValue, JsValue, JsValue) -> JsValue,
-            move |value: JsValue, _, _| {
-                let val = Self::format_value(value.as_f64().unwrap());
-                JsValue::from_str(&val)
+            dyn Fn(JsValue, JsValue) -> JsValue,
+            move |item: JsValue, data: JsValue| {
+                let index = Reflect::get(&item, &JsValue::from_str("datasetIndex"))
+                    .unwrap_or(JsValue::UNDEFINED);
+                let dataset = Reflect::get(&data, &JsValue::from_str("datasets"))
+                    .and_then(|datasets| Reflect::get(&datasets, &index))
+                    .unwrap_or(JsValue::UNDEFINED);
+                let label = Reflect::get(&dataset, &JsValue::from_str("label"))
+                    .ok()
+                    .and_then(|l| l.as_string())
+                    .unwrap_or_default();
+                let value = Reflect::get(&item, &JsValue::from_str("value"))
+                    .ok()
+                    .and_then(|v| v.as_string())
+                    .and_then(|v| v.parse().ok());
+                let formatter = index
+                    .as_f64()
+                    .and_then(|i| dataset_formatters.get(i as usize));
+                match (value, formatter) {
+                    (Some(value), Some(formatter)) => {
+                        JsValue::from_str(&format!("{}: {}", label, formatter.format(value)))
+                    }
+                    _ => JsValue::from_str(&label),
+                }
             }
         );
 
+        let thresholds = config.thresholds.clone();
+        let annotations_state = Rc::clone(&state);
+        let annotations_cb = closure!(closures, dyn Fn(JsValue), move |chart: JsValue| {
+            let tags = &annotations_state.borrow().tags;
+            Self::draw_annotations(chart.unchecked_ref(), tags, &thresholds);
+        });
+        // Let the Y axis cover thresholds even when no value is close to them.
+        let threshold_values = config.thresholds.iter().map(|t| t.value);
+        let suggested_min = threshold_values.clone().reduce(f64::min);
+        let suggested_max = threshold_values.reduce(f64::max);
+
+        let y_axes = js_arr![Self::y_axis(
+            Y_AXIS_ID,
+            "left",
+            &config.axis,
+            &left_ticks_cb,
+            suggested_min,
+            suggested_max
+        )
+        .as_ref()];
+        if let Some(ticks_cb) = right_ticks_cb {
+            y_axes.push(
+                Self::y_axis(
+                    RIGHT_Y_AXIS_ID,
+                    "right",
+                    &config.right_axis,
+                    &ticks_cb,
+                    None,
+                    None,
+                )
+                .as_ref(),
+            );
+        }
+        let scales = js_obj! {
+            yAxes => y_axes.as_ref(),
+        };
+        if config.x_axis != XAxis::Commit {
+            Reflect::set(
+                &scales,
+                &JsValue::from_str("xAxes"),
+                js_arr![js_obj! {
+                    type => js_ref!("time"),
+                    distribution => js_ref!("linear"),
+                    // Keep bars at both ends within the chart area.
+                    offset => js_ref!(config.chart_type == ChartType::Bar),
+                }
+                .as_ref()]
+                .as_ref(),
+            )
+            .expect("error setting js attribute: xAxes");
+        }
+
         let chart_config = js_obj! {
-            type => js_ref!("line"),
-            data => js_obj! {
-                labels => &labels.into_iter().map(JsValue::from).collect::<Array>(),
-                datasets => js_arr![js_obj! {
-                    backgroundColor => js_ref!(BACKGROUND_COLOR),
-                    borderColor => js_ref!(BORDER_COLOR),
-                    label => js_ref!(&config.title),
-                    data => &datapoints.into_iter().map(JsValue::from).collect::<Array>(),
-                }.as_ref()].as_ref(),
-            }.as_ref(),
+            type => js_ref!(match config.chart_type {
+                ChartType::Bar => "bar",
+                ChartType::Line | ChartType::Step | ChartType::Scatter => "line",
+            }),
+            data => chart_data.as_ref(),
             options => js_obj! {
                 tooltips => js_obj! {
                     callbacks => js_obj! {
                         title => title_cb.as_ref(),
+                        label => label_cb.as_ref(),
                     }.as_ref(),
                 }.as_ref(),
-                scales => js_obj! {
-                    yAxes => js_arr![js_obj! {
-                        ticks => js_obj! {
-                            beginAtZero => js_ref!(true),
-                            callback => yaxis_cb.as_ref()
-                        }.as_ref(),
-                    }.as_ref()].as_ref(),
-                }.as_ref(),
+                scales => scales.as_ref(),
                 onClick => on_click.as_ref(),
+                onHover => on_hover.as_ref(),
             }.as_ref(),
+            plugins => js_arr![js_obj! {
+                afterDraw => annotations_cb.as_ref(),
+            }.as_ref()].as_ref(),
         };
 
-        let chart = Chart::new(target, chart_config);
+        let document = yew::utils::document();
+        let canvas = document.create_element("canvas").expect("create canvas");
+        canvas
+            .set_attribute("width", CANVAS_WIDTH)
+            .expect("set width");
+        canvas
+            .set_attribute("height", CANVAS_HEIGHT)
+            .expect("set height");
+        target.append_child(&canvas).expect("append canvas");
+        let chart = Chart::new(canvas.clone(), chart_config);
         ChartJs {
             chart,
+            canvas,
+            config: config.clone(),
+            state,
             _closures: closures,
         }
     }
+
+    fn update(&mut self, data: &Rc<CommitViewData>) {
+        *self.state.borrow_mut() = ChartData::new(data);
+        let new_data = Self::build_data(&self.config, data, &self.state.borrow().commits);
+        let current = match Reflect::get(&self.chart, &JsValue::from_str("data")) {
+            Ok(current) if current.is_object() => current,
+            _ => {
+                error!("Chart has no data to update, leaving it as is");
+                return;
+            }
+        };
+        let labels = Reflect::get(&new_data, &JsValue::from_str("labels")).unwrap_or_default();
+        if let Err(e) = Reflect::set(&current, &JsValue::from_str("labels"), &labels) {
+            error!("Failed to update labels of chart: {:?}", e);
+            return;
+        }
+        // Datasets are updated in place rather than replaced, so that Chart.js can animate
+        // from the current state. The number of datasets doesn't change as long as the config
+        // is the same.
+        let current = match Reflect::get(&current, &JsValue::from_str("datasets")) {
+            Ok(datasets) if Array::is_array(&datasets) => Array::from(&datasets),
+            _ => {
+                error!("Chart has no datasets to update, leaving it as is");
+                return;
+            }
+        };
+        let datasets = Reflect::get(&new_data, &JsValue::from_str("datasets"))
+            .map(|datasets| Array::from(&datasets))
+            .unwrap_or_else(|_| Array::new());
+        for (existing, dataset) in current.iter().zip(datasets.iter()) {
+            Object::assign(
+                existing.unchecked_ref::<Object>(),
+                dataset.unchecked_ref::<Object>(),
+            );
+        }
+        self.chart.update();
+    }
 }
 
 impl Drop for ChartJs {
     fn drop(&mut self) {
         self.chart.destroy();
+        self.canvas.remove();
+    }
+}
+
+/// Returns true if the value is 1, 2 or 5 times a power of 10.
+fn is_log_major_tick(value: f64) -> bool {
+    if value <= 0.0 {
+        return false;
     }
+    let mantissa = value / 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0].iter().any(|m| (mantissa - m).abs() < 1e-6)
 }
@@ -1,12 +1,115 @@
+#[cfg(feature = "chartjs")]
 pub mod chartjs;
+pub mod svg;
 
-use crate::component::CommitViewData;
+use crate::commit::CommitInfo;
+use crate::component::{CommitSpan, CommitViewData};
+use crate::config::{Axis, ChartType, Dates, Overlay, Series, Threshold, Unit, XAxis};
+use crate::dataset::Status;
+use crate::hooks::{self, Hooks};
+use log::*;
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
 use web_sys::Element;
 
+pub const BORDER_COLOR: &str = "rgba(1,169,244,0.5)";
+pub const TAG_LINE_COLOR: &str = "rgba(255,152,0,0.8)";
+pub const THRESHOLD_COLOR: &str = "rgba(244,67,54,0.8)";
+pub const SERIES_COLORS: &[&str] = &[
+    "rgba(0,150,136,0.7)",
+    "rgba(103,58,183,0.7)",
+    "rgba(121,85,72,0.7)",
+    "rgba(96,125,139,0.7)",
+];
+pub const OVERLAY_COLORS: &[&str] = &[
+    "rgba(233,30,99,0.7)",
+    "rgba(76,175,80,0.7)",
+    "rgba(156,39,176,0.7)",
+    "rgba(255,193,7,0.7)",
+];
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     pub title: String,
+    pub thresholds: Vec<Threshold>,
+    /// Overlays to draw, which are enabled by user.
+    pub overlays: Vec<Overlay>,
+    pub x_axis: XAxis,
+    pub dates: Dates,
+    pub axis: Axis,
+    pub unit: Option<Unit>,
+    pub series: Vec<Series>,
+    pub right_axis: Axis,
+    pub chart_type: ChartType,
+    pub span_gaps: bool,
+    pub hooks: Hooks,
+}
+
+impl Config {
+    /// Returns the color to draw the datapoint of the value in, which marks violated threshold.
+    pub fn point_color(&self, value: Option<f64>) -> &str {
+        let violated = self
+            .thresholds
+            .iter()
+            .find(|t| value.map(|v| t.is_violated_by(v)).unwrap_or(false));
+        match violated {
+            Some(t) => t.color.as_deref().unwrap_or(THRESHOLD_COLOR),
+            None => BORDER_COLOR,
+        }
+    }
+}
+
+/// Returns the datapoint of the commit to pass to hooks.
+pub fn hook_point(config: &Config, data: &CommitViewData, commit: &CommitInfo) -> JsValue {
+    hooks::Point::new(&config.title, &config.series, data, commit).to_value()
 }
 
+/// Handle click on the datapoint by the hook, or by opening the page of the commit at the URL
+/// if it isn't set.
+pub fn click_point(config: &Config, point: &JsValue, url: &str) {
+    if config.hooks.point_click(point) {
+        return;
+    }
+    debug!("Opening {}", url);
+    if let Err(e) = yew::utils::window().open_with_url_and_target(url, "_blank") {
+        error!("Failed to open {}: {:?}", url, e);
+    }
+}
+
+/// Chart renders view data into the target element. Instances are dropped when the chart is
+/// replaced, so implementations should clean up what they added to the target on drop.
 pub trait Chart {
-    fn create(target: Element, config: &Config, data: &CommitViewData) -> Self;
+    /// Create the chart, which might keep the data shared to describe datapoints on events.
+    fn create(target: Element, config: &Config, data: &Rc<CommitViewData>) -> Self;
+
+    /// Replace data of the chart keeping its config.
+    fn update(&mut self, data: &Rc<CommitViewData>);
+}
+
+/// Returns the title of tooltips for the commit, which tells why its value is missing if so.
+pub fn describe_commit(
+    commit: &CommitInfo,
+    span: Option<&CommitSpan>,
+    status: Option<&Status>,
+    dates: &Dates,
+) -> String {
+    let mut title = format!(
+        "{}\n{} {}",
+        commit.date_str(dates),
+        commit.sha_short(),
+        commit.message_headline()
+    );
+    if let Some(span) = span {
+        title.push('\n');
+        title.push_str(&span.describe());
+    }
+    if let Some(pr) = commit.merged_pull_request() {
+        title.push_str(&format!("\nMerged PR #{}", pr));
+    }
+    match status {
+        Some(Status::Ok) => {}
+        Some(status) => title.push_str(&format!("\n{}", status)),
+        None => title.push_str("\nLoading metadata"),
+    }
+    title
 }
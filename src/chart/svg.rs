@@ -0,0 +1,949 @@
+use crate::chart::{
+    self, BORDER_COLOR, OVERLAY_COLORS, SERIES_COLORS, TAG_LINE_COLOR, THRESHOLD_COLOR,
+};
+use crate::commit::CommitInfo;
+use crate::component::CommitViewData;
+use crate::config::{Axis, AxisPosition, ChartType, DateField, Dates, Scale, XAxis};
+use crate::format::{self, ValueFormatter};
+use crate::overlay;
+use log::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, MouseEvent};
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 400.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 30.0;
+/// Horizontal margin to leave for tick labels of a Y axis.
+const AXIS_MARGIN: f64 = 70.0;
+const PLAIN_MARGIN: f64 = 20.0;
+const Y_TICKS: usize = 5;
+const X_LABELS: usize = 6;
+const X_LABEL_FORMAT: &str = "%Y-%m-%d";
+const POINT_RADIUS: f64 = 3.0;
+const MERGE_POINT_RADIUS: f64 = 5.0;
+const BAR_FILL_RATIO: f64 = 0.8;
+const GRID_COLOR: &str = "rgba(0,0,0,0.1)";
+const TEXT_COLOR: &str = "#666";
+/// Approximate width of a character in legends, to lay them out without measuring text.
+const LEGEND_CHAR_WIDTH: f64 = 6.5;
+const TOOLTIP_STYLE: &str = "position: absolute; top: 0; white-space: pre; pointer-events: none; \
+                             background: rgba(0,0,0,0.8); color: #fff; padding: 4px 6px; \
+                             border-radius: 3px; font-size: 11px;";
+
+/// SVG document of a chart, along with what's needed to make it interactive.
+pub struct Rendered {
+    pub svg: String,
+    /// Tooltip text of each commit in chronological order, with its X position in ratio to the
+    /// width of the chart.
+    pub tooltips: Vec<(f64, String)>,
+}
+
+/// Maps values onto the vertical range of the plot area.
+struct YScale {
+    min: f64,
+    max: f64,
+    log: bool,
+    top: f64,
+    bottom: f64,
+}
+
+impl YScale {
+    fn new(axis: &Axis, values: &[f64], top: f64, bottom: f64) -> Self {
+        let log = axis.scale == Scale::Logarithmic;
+        let values = values
+            .iter()
+            .copied()
+            .filter(|v| v.is_finite() && (!log || *v > 0.0));
+        let (mut min, mut max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+        if min > max {
+            // No values to fit in.
+            min = if log { 1.0 } else { 0.0 };
+            max = if log { 10.0 } else { 1.0 };
+        }
+        if !log && axis.begin_at_zero {
+            min = min.min(0.0);
+            max = max.max(0.0);
+        }
+        if min == max {
+            if log {
+                min /= 10.0;
+                max *= 10.0;
+            } else {
+                min -= 1.0;
+                max += 1.0;
+            }
+        }
+        if log {
+            min = 10f64.powf(min.log10().floor());
+            max = 10f64.powf(max.log10().ceil());
+        } else {
+            let step = nice_step((max - min) / Y_TICKS as f64);
+            min = (min / step).floor() * step;
+            max = (max / step).ceil() * step;
+        }
+        let (auto_min, auto_max) = (min, max);
+        let mut min = axis.min.unwrap_or(min);
+        let mut max = axis.max.unwrap_or(max);
+        if log {
+            // Non-positive bounds can't be placed on logarithmic scale.
+            if min <= 0.0 {
+                min = auto_min;
+            }
+            if max <= 0.0 {
+                max = auto_max;
+            }
+        }
+        if min >= max {
+            // A bound given by config is beyond the other one, which is kept if given too.
+            match (axis.min, axis.max) {
+                (Some(_), None) if log => max = min * 10.0,
+                (Some(_), None) => max = min + (auto_max - auto_min),
+                (None, Some(_)) if log => min = max / 10.0,
+                (None, Some(_)) => min = max - (auto_max - auto_min),
+                _ => {
+                    min = auto_min;
+                    max = auto_max;
+                }
+            }
+        }
+        Self {
+            min,
+            max,
+            log,
+            top,
+            bottom,
+        }
+    }
+
+    fn ticks(&self) -> Vec<f64> {
+        if self.log {
+            let (from, to) = (self.min.log10().floor(), self.max.log10().ceil());
+            let multiples: &[f64] = if to - from > 3.0 {
+                &[1.0]
+            } else {
+                &[1.0, 2.0, 5.0]
+            };
+            let mut ticks = Vec::new();
+            let mut exp = from;
+            while exp <= to {
+                for m in multiples {
+                    let v = m * 10f64.powf(exp);
+                    if v >= self.min && v <= self.max {
+                        ticks.push(v);
+                    }
+                }
+                exp += 1.0;
+            }
+            ticks
+        } else {
+            let step = nice_step((self.max - self.min) / Y_TICKS as f64);
+            let first = (self.min / step).ceil() * step;
+            (0..)
+                .map(|i| first + step * i as f64)
+                .take_while(|v| *v <= self.max + step * 1e-9)
+                .take(Y_TICKS * 4)
+                .collect()
+        }
+    }
+
+    fn pixel(&self, value: f64) -> Option<f64> {
+        let ratio = if self.log {
+            if value <= 0.0 {
+                return None;
+            }
+            (value.log10() - self.min.log10()) / (self.max.log10() - self.min.log10())
+        } else {
+            (value - self.min) / (self.max - self.min)
+        };
+        Some(self.bottom - ratio * (self.bottom - self.top))
+    }
+
+    /// Returns the vertical position to draw bars from.
+    fn baseline(&self) -> f64 {
+        if self.log || self.min > 0.0 {
+            self.bottom
+        } else if self.max < 0.0 {
+            self.top
+        } else {
+            self.pixel(0.0).unwrap_or(self.bottom)
+        }
+    }
+}
+
+/// Returns a step between ticks which is 1, 2 or 5 times a power of 10, close to `raw`.
+fn nice_step(raw: f64) -> f64 {
+    if raw.is_nan() || raw.is_infinite() || raw <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let normalized = raw / magnitude;
+    let nice = if normalized <= 1.0 {
+        1.0
+    } else if normalized <= 2.0 {
+        2.0
+    } else if normalized <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Build the `d` attribute of a path through the points, breaking it at missing points unless
+/// `span_gaps` is set.
+fn line_path(points: &[Option<(f64, f64)>], span_gaps: bool, stepped: bool) -> String {
+    let mut d = String::new();
+    let mut pen_down = false;
+    for point in points {
+        match point {
+            Some((x, y)) if pen_down && stepped => d.push_str(&format!("H{:.1}V{:.1}", x, y)),
+            Some((x, y)) if pen_down => d.push_str(&format!("L{:.1} {:.1}", x, y)),
+            Some((x, y)) => {
+                d.push_str(&format!("M{:.1} {:.1}", x, y));
+                pen_down = true;
+            }
+            None if !span_gaps => pen_down = false,
+            None => {}
+        }
+    }
+    d
+}
+
+fn point_shape(x: f64, y: f64, color: &str, merge: bool) -> String {
+    // Colors of violated thresholds come from the config.
+    let color = escape(color);
+    if merge {
+        // Rotated square, which is how merge commits are marked by the Chart.js renderer too.
+        let r = MERGE_POINT_RADIUS;
+        format!(
+            r#"<path d="M{:.1} {:.1}L{:.1} {:.1}L{:.1} {:.1}L{:.1} {:.1}Z" fill="{}" stroke="{}"/>"#,
+            x,
+            y - r,
+            x + r,
+            y,
+            x,
+            y + r,
+            x - r,
+            y,
+            color,
+            color
+        )
+    } else {
+        format!(
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}" stroke="{}"/>"#,
+            x, y, POINT_RADIUS, color, color
+        )
+    }
+}
+
+/// Render the chart into an SVG document. This doesn't depend on DOM so the result can also be
+/// embedded into static reports.
+pub fn render(config: &chart::Config, data: &CommitViewData) -> Rendered {
+    let commits: Vec<&CommitInfo> = data.commits.iter().rev().collect();
+    let n = commits.len();
+    let has_right = config
+        .series
+        .iter()
+        .any(|s| s.y_axis == AxisPosition::Right);
+    let (left, right) = (
+        AXIS_MARGIN,
+        WIDTH - if has_right { AXIS_MARGIN } else { PLAIN_MARGIN },
+    );
+    let (top, bottom) = (MARGIN_TOP, HEIGHT - MARGIN_BOTTOM);
+
+    // Values of the main value, extra series then overlays, in chronological order.
+    let main_values: Vec<Option<f64>> = commits
+        .iter()
+        .map(|c| data.metadata.get(&c.sha).copied())
+        .collect();
+    let series_values: Vec<Vec<Option<f64>>> = (0..config.series.len())
+        .map(|i| {
+            commits
+                .iter()
+                .map(|c| data.series.get(i).and_then(|v| v.get(&c.sha)).copied())
+                .collect()
+        })
+        .collect();
+    let xs: Vec<f64> = commits
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c.timestamp_millis(config.x_axis).unwrap_or(i as f64))
+        .collect();
+    let overlay_values: Vec<Vec<Option<f64>>> = config
+        .overlays
+        .iter()
+        .map(|ov| overlay::compute(ov, &xs, &main_values))
+        .collect();
+
+    let mut left_values: Vec<f64> = main_values.iter().flatten().copied().collect();
+    left_values.extend(config.thresholds.iter().map(|t| t.value));
+    left_values.extend(overlay_values.iter().flatten().flatten());
+    let mut right_values = Vec::new();
+    for (series, values) in config.series.iter().zip(&series_values) {
+        match series.y_axis {
+            AxisPosition::Left => left_values.extend(values.iter().flatten()),
+            AxisPosition::Right => right_values.extend(values.iter().flatten()),
+        }
+    }
+    let left_scale = YScale::new(&config.axis, &left_values, top, bottom);
+    let right_scale = YScale::new(&config.right_axis, &right_values, top, bottom);
+    let scale_of = |position: AxisPosition| match position {
+        AxisPosition::Left => &left_scale,
+        AxisPosition::Right => &right_scale,
+    };
+
+    let bars = config.chart_type == ChartType::Bar;
+    let (x_min, x_max) = xs
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| {
+            (lo.min(*x), hi.max(*x))
+        });
+    // Leave a half of spacing between commits at both ends so that bars fit in.
+    let pad = if bars && n > 1 {
+        (x_max - x_min) / (n - 1) as f64 / 2.0
+    } else if bars {
+        0.5
+    } else {
+        0.0
+    };
+    let x_pixel = |x: f64| {
+        let (lo, hi) = (x_min - pad, x_max + pad);
+        if hi > lo {
+            left + (x - lo) / (hi - lo) * (right - left)
+        } else {
+            (left + right) / 2.0
+        }
+    };
+    let px: Vec<f64> = xs.iter().map(|x| x_pixel(*x)).collect();
+
+    let formatters = format::series_formatters(
+        config.unit.as_ref(),
+        &config.axis,
+        &config.series,
+        &config.right_axis,
+    );
+    let right_formatter = config
+        .series
+        .iter()
+        .zip(&formatters[1..])
+        .find(|(s, _)| s.y_axis == AxisPosition::Right)
+        .map(|(_, f)| f.clone());
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="100%" font-family="sans-serif" font-size="11" fill="{}">"#,
+        WIDTH, HEIGHT, TEXT_COLOR
+    );
+
+    // Legend
+    let mut legends = vec![(config.title.as_str(), BORDER_COLOR)];
+    for (i, series) in config.series.iter().enumerate() {
+        legends.push((&series.title, SERIES_COLORS[i % SERIES_COLORS.len()]));
+    }
+    let overlay_labels: Vec<_> = config.overlays.iter().map(|ov| ov.label()).collect();
+    for (i, label) in overlay_labels.iter().enumerate() {
+        legends.push((label, OVERLAY_COLORS[i % OVERLAY_COLORS.len()]));
+    }
+    let mut legend_x = left;
+    for (label, color) in legends {
+        svg.push_str(&format!(
+            r#"<rect x="{:.1}" y="8" width="10" height="10" fill="{}"/><text x="{:.1}" y="17">{}</text>"#,
+            legend_x,
+            color,
+            legend_x + 14.0,
+            escape(label)
+        ));
+        legend_x += 24.0 + label.chars().count() as f64 * LEGEND_CHAR_WIDTH;
+    }
+
+    // Axes
+    svg.push_str(&axis_ticks(
+        &left_scale,
+        &formatters[0],
+        left,
+        right,
+        AxisPosition::Left,
+    ));
+    svg.push_str(&axis_title(&config.axis, AxisPosition::Left, top, bottom));
+    if let Some(formatter) = &right_formatter {
+        svg.push_str(&axis_ticks(
+            &right_scale,
+            formatter,
+            left,
+            right,
+            AxisPosition::Right,
+        ));
+        svg.push_str(&axis_title(
+            &config.right_axis,
+            AxisPosition::Right,
+            top,
+            bottom,
+        ));
+    }
+    let label_dates = Dates {
+        show: match config.x_axis {
+            XAxis::CommitDate => DateField::Commit,
+            XAxis::Commit | XAxis::AuthorDate => DateField::Author,
+        },
+        format: X_LABEL_FORMAT.to_string(),
+        timezone: config.dates.timezone,
+    };
+    let label_every = n.div_ceil(X_LABELS).max(1);
+    for (i, commit) in commits.iter().enumerate().step_by(label_every) {
+        let label = match config.x_axis {
+            XAxis::Commit => commit.sha_short().to_string(),
+            XAxis::AuthorDate | XAxis::CommitDate => commit.date_str(&label_dates),
+        };
+        svg.push_str(&format!(
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            px[i],
+            bottom + 15.0,
+            escape(&label)
+        ));
+    }
+    svg.push_str(&format!(
+        r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}"/>"#,
+        left, bottom, right, bottom, TEXT_COLOR
+    ));
+
+    // Datapoints are drawn in a nested SVG, which clips what's out of the plot area, e.g, when
+    // the axis has fixed bounds.
+    svg.push_str(&format!(
+        r#"<svg x="{l:.1}" y="{t:.1}" width="{w:.1}" height="{h:.1}" viewBox="{l:.1} {t:.1} {w:.1} {h:.1}">"#,
+        l = left,
+        t = top,
+        w = right - left,
+        h = bottom - top
+    ));
+    let bar_groups = 1 + config.series.len();
+    let bar_width = (right - left) / n.max(1) as f64 * BAR_FILL_RATIO / bar_groups as f64;
+    let draw_values =
+        |svg: &mut String, values: &[Option<f64>], scale: &YScale, color: &str, group: usize| {
+            let points: Vec<_> = values
+                .iter()
+                .zip(&px)
+                .map(|(v, x)| Some((*x, scale.pixel((*v)?)?)))
+                .collect();
+            match config.chart_type {
+                ChartType::Line | ChartType::Step => svg.push_str(&format!(
+                    r#"<path d="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+                    line_path(
+                        &points,
+                        config.span_gaps,
+                        config.chart_type == ChartType::Step
+                    ),
+                    color
+                )),
+                ChartType::Bar => {
+                    let offset = (group as f64 - bar_groups as f64 / 2.0) * bar_width;
+                    for (x, y) in points.iter().flatten() {
+                        let base = scale.baseline();
+                        svg.push_str(&format!(
+                            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+                            x + offset,
+                            y.min(base),
+                            bar_width,
+                            (y - base).abs(),
+                            color
+                        ));
+                    }
+                }
+                ChartType::Scatter => {}
+            }
+            points
+        };
+    let main_points = draw_values(&mut svg, &main_values, &left_scale, BORDER_COLOR, 0);
+    if !bars {
+        for (i, point) in main_points.iter().enumerate() {
+            if let Some((x, y)) = point {
+                let color = config.point_color(main_values[i]);
+                svg.push_str(&point_shape(*x, *y, color, commits[i].is_merge()));
+            }
+        }
+    }
+    for (i, (series, values)) in config.series.iter().zip(&series_values).enumerate() {
+        let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+        let points = draw_values(&mut svg, values, scale_of(series.y_axis), color, i + 1);
+        if !bars {
+            for (x, y) in points.iter().flatten() {
+                svg.push_str(&point_shape(*x, *y, color, false));
+            }
+        }
+    }
+    for (i, values) in overlay_values.iter().enumerate() {
+        let points: Vec<_> = values
+            .iter()
+            .zip(&px)
+            .map(|(v, x)| Some((*x, left_scale.pixel((*v)?)?)))
+            .collect();
+        svg.push_str(&format!(
+            r#"<path d="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            line_path(&points, true, false),
+            OVERLAY_COLORS[i % OVERLAY_COLORS.len()]
+        ));
+    }
+
+    // Annotations
+    for threshold in &config.thresholds {
+        let y = match left_scale.pixel(threshold.value) {
+            Some(y) => y,
+            None => continue,
+        };
+        let color = threshold.color.as_deref().unwrap_or(THRESHOLD_COLOR);
+        svg.push_str(&format!(
+            r#"<line x1="{l:.1}" y1="{y:.1}" x2="{r:.1}" y2="{y:.1}" stroke="{c}" stroke-dasharray="4 4"/><text x="{tx:.1}" y="{ty:.1}" fill="{c}" font-size="10">{label}</text>"#,
+            l = left,
+            r = right,
+            y = y,
+            c = escape(color),
+            tx = left + 2.0,
+            ty = y - 2.0,
+            label = escape(&threshold.label())
+        ));
+    }
+    for (i, commit) in commits.iter().enumerate() {
+        if let Some(names) = data.tags.get(&commit.sha) {
+            svg.push_str(&format!(
+                r#"<line x1="{x:.1}" y1="{t:.1}" x2="{x:.1}" y2="{b:.1}" stroke="{c}" stroke-dasharray="4 4"/><text x="{tx:.1}" y="{ty:.1}" fill="{c}" font-size="10">{label}</text>"#,
+                x = px[i],
+                t = top,
+                b = bottom,
+                c = TAG_LINE_COLOR,
+                tx = px[i] + 2.0,
+                ty = top + 10.0,
+                label = escape(&names.join(", "))
+            ));
+        }
+    }
+
+    // Transparent columns over each commit, which receive mouse events for tooltips.
+    let mut tooltips = Vec::with_capacity(n);
+    for (i, commit) in commits.iter().enumerate() {
+        let from = if i == 0 {
+            left
+        } else {
+            (px[i - 1] + px[i]) / 2.0
+        };
+        let to = if i + 1 == n {
+            right
+        } else {
+            (px[i] + px[i + 1]) / 2.0
+        };
+        svg.push_str(&format!(
+            r#"<rect data-index="{}" x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="transparent" cursor="pointer"/>"#,
+            i,
+            from,
+            top,
+            (to - from).max(0.0),
+            bottom - top
+        ));
+
+        let mut text = chart::describe_commit(
+            commit,
+            data.spans.get(&commit.sha),
+            data.statuses.get(&commit.sha),
+            &config.dates,
+        );
+        let mut add_value = |label: &str, value: Option<f64>, formatter: &ValueFormatter| {
+            if let Some(value) = value {
+                text.push_str(&format!("\n{}: {}", label, formatter.format(value)));
+            }
+        };
+        add_value(&config.title, main_values[i], &formatters[0]);
+        for (j, series) in config.series.iter().enumerate() {
+            add_value(&series.title, series_values[j][i], &formatters[j + 1]);
+        }
+        for (label, values) in overlay_labels.iter().zip(&overlay_values) {
+            add_value(label, values[i], &formatters[0]);
+        }
+        tooltips.push((px[i] / WIDTH, text));
+    }
+    svg.push_str("</svg></svg>");
+
+    Rendered { svg, tooltips }
+}
+
+fn axis_ticks(
+    scale: &YScale,
+    formatter: &ValueFormatter,
+    left: f64,
+    right: f64,
+    position: AxisPosition,
+) -> String {
+    let mut svg = String::new();
+    for tick in scale.ticks() {
+        let y = match scale.pixel(tick) {
+            Some(y) => y,
+            None => continue,
+        };
+        let (x, anchor) = match position {
+            AxisPosition::Left => (left - 6.0, "end"),
+            AxisPosition::Right => (right + 6.0, "start"),
+        };
+        if position == AxisPosition::Left {
+            svg.push_str(&format!(
+                r#"<line x1="{:.1}" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" stroke="{}"/>"#,
+                left,
+                right,
+                GRID_COLOR,
+                y = y
+            ));
+        }
+        svg.push_str(&format!(
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="{}">{}</text>"#,
+            x,
+            y + 4.0,
+            anchor,
+            escape(&formatter.format(tick))
+        ));
+    }
+    svg
+}
+
+fn axis_title(axis: &Axis, position: AxisPosition, top: f64, bottom: f64) -> String {
+    let title = match &axis.title {
+        Some(title) => title,
+        None => return String::new(),
+    };
+    let x = match position {
+        AxisPosition::Left => 12.0,
+        AxisPosition::Right => WIDTH - 12.0,
+    };
+    let y = (top + bottom) / 2.0;
+    format!(
+        r#"<text x="{x:.1}" y="{y:.1}" text-anchor="middle" transform="rotate(-90 {x:.1} {y:.1})">{}</text>"#,
+        escape(title),
+        x = x,
+        y = y
+    )
+}
+
+/// Returns the index of the commit which the mouse event happened on.
+fn event_index(event: &MouseEvent) -> Option<usize> {
+    event
+        .target()?
+        .dyn_into::<Element>()
+        .ok()?
+        .get_attribute("data-index")?
+        .parse()
+        .ok()
+}
+
+type Listener = Closure<dyn Fn(MouseEvent)>;
+
+/// What mouse events on the chart refer to, which is replaced by update.
+struct Interaction {
+    tooltips: Vec<(f64, String)>,
+    data: Rc<CommitViewData>,
+    /// Index of the datapoint hovered last, not to call the hook on every mouseover bubbling
+    /// from its elements.
+    hovered: Option<usize>,
+}
+
+impl Interaction {
+    fn new(tooltips: Vec<(f64, String)>, data: &Rc<CommitViewData>) -> Self {
+        Self {
+            tooltips,
+            data: Rc::clone(data),
+            hovered: None,
+        }
+    }
+
+    /// Returns the commit of the datapoint at the index, which is in chronological order.
+    fn commit(&self, index: usize) -> Option<&CommitInfo> {
+        self.data.commits.iter().rev().nth(index)
+    }
+}
+
+/// Chart rendered as SVG by this crate itself, without depending on any JS library.
+pub struct SvgChart {
+    container: Element,
+    /// Element holding the SVG document, separated from the tooltip to replace it on update.
+    plot: Element,
+    config: chart::Config,
+    interaction: Rc<RefCell<Interaction>>,
+    _listeners: Vec<Listener>,
+}
+
+impl chart::Chart for SvgChart {
+    fn create(target: Element, config: &chart::Config, data: &Rc<CommitViewData>) -> Self {
+        let rendered = render(config, data);
+        let document = yew::utils::document();
+        let container = document.create_element("div").expect("create div");
+        container.set_class_name("loupe-svg-chart");
+        container
+            .set_attribute("style", "position: relative")
+            .expect("set style");
+        let plot = document.create_element("div").expect("create div");
+        plot.set_inner_html(&rendered.svg);
+        container.append_child(&plot).expect("append plot");
+        let tooltip = document.create_element("div").expect("create div");
+        tooltip.set_class_name("loupe-svg-tooltip");
+        tooltip
+            .set_attribute("style", "display: none")
+            .expect("set style");
+        container.append_child(&tooltip).expect("append tooltip");
+        target.append_child(&container).expect("append chart");
+
+        let interaction = Rc::new(RefCell::new(Interaction::new(rendered.tooltips, data)));
+        let hide = {
+            let tooltip = tooltip.clone();
+            let interaction = Rc::clone(&interaction);
+            move || {
+                interaction.borrow_mut().hovered = None;
+                let _ = tooltip.set_attribute("style", "display: none");
+            }
+        };
+
+        let mut listeners: Vec<(&str, Listener)> = Vec::new();
+        let hide_on_over = hide.clone();
+        let over_interaction = Rc::clone(&interaction);
+        let over_config = config.clone();
+        listeners.push((
+            "mouseover",
+            Closure::wrap(Box::new(move |event: MouseEvent| {
+                let point = {
+                    let mut interaction = over_interaction.borrow_mut();
+                    let index = event_index(&event);
+                    let (x, text) = match index.and_then(|i| interaction.tooltips.get(i)) {
+                        Some(tooltip) => tooltip,
+                        None => {
+                            drop(interaction);
+                            return hide_on_over();
+                        }
+                    };
+                    // Place the tooltip on the side having more room.
+                    let position = if *x < 0.5 {
+                        format!("left: {:.1}%", x * 100.0)
+                    } else {
+                        format!("right: {:.1}%", (1.0 - x) * 100.0)
+                    };
+                    tooltip.set_text_content(Some(text));
+                    let _ =
+                        tooltip.set_attribute("style", &format!("{} {}", TOOLTIP_STYLE, position));
+                    if interaction.hovered == index {
+                        return;
+                    }
+                    interaction.hovered = index;
+                    match index.and_then(|i| interaction.commit(i)) {
+                        Some(commit) if over_config.hooks.point_hover.is_some() => {
+                            chart::hook_point(&over_config, &interaction.data, commit)
+                        }
+                        _ => return,
+                    }
+                };
+                over_config.hooks.point_hover(&point);
+            }) as Box<dyn Fn(MouseEvent)>),
+        ));
+        listeners.push((
+            "mouseleave",
+            Closure::wrap(Box::new(move |_: MouseEvent| hide()) as Box<dyn Fn(MouseEvent)>),
+        ));
+        let click_interaction = Rc::clone(&interaction);
+        let click_config = config.clone();
+        listeners.push((
+            "click",
+            Closure::wrap(Box::new(move |event: MouseEvent| {
+                let (point, url) = {
+                    let interaction = click_interaction.borrow();
+                    match event_index(&event).and_then(|i| interaction.commit(i)) {
+                        Some(commit) => (
+                            chart::hook_point(&click_config, &interaction.data, commit),
+                            commit.view_url.clone(),
+                        ),
+                        None => return,
+                    }
+                };
+                debug!("SvgChart onClick: link={}", url);
+                chart::click_point(&click_config, &point, &url);
+            }) as Box<dyn Fn(MouseEvent)>),
+        ));
+        for (event, listener) in &listeners {
+            container
+                .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+                .expect("add event listener");
+        }
+
+        SvgChart {
+            container,
+            plot,
+            config: config.clone(),
+            interaction,
+            _listeners: listeners.into_iter().map(|(_, l)| l).collect(),
+        }
+    }
+
+    fn update(&mut self, data: &Rc<CommitViewData>) {
+        let rendered = render(&self.config, data);
+        self.plot.set_inner_html(&rendered.svg);
+        *self.interaction.borrow_mut() = Interaction::new(rendered.tooltips, data);
+    }
+}
+
+impl Drop for SvgChart {
+    fn drop(&mut self) {
+        self.container.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scale(axis: &Axis, values: &[f64]) -> YScale {
+        YScale::new(axis, values, 0.0, 100.0)
+    }
+
+    fn bounds(scale: &YScale) -> (f64, f64) {
+        (scale.min, scale.max)
+    }
+
+    fn log_axis() -> Axis {
+        Axis {
+            scale: Scale::Logarithmic,
+            ..Axis::default()
+        }
+    }
+
+    #[test]
+    fn test_nice_step() {
+        assert_eq!(nice_step(0.7), 1.0);
+        assert_eq!(nice_step(1.0), 1.0);
+        assert_eq!(nice_step(1.5), 2.0);
+        assert_eq!(nice_step(3.0), 5.0);
+        assert_eq!(nice_step(7.0), 10.0);
+        assert_eq!(nice_step(2500.0), 5000.0);
+        assert!((nice_step(0.03) - 0.05).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_nice_step_of_degenerate_input() {
+        for raw in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(nice_step(*raw), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_scale_without_values() {
+        assert_eq!(bounds(&scale(&Axis::default(), &[])), (0.0, 1.0));
+        assert_eq!(bounds(&scale(&log_axis(), &[])), (1.0, 10.0));
+        assert_eq!(bounds(&scale(&Axis::default(), &[f64::NAN])), (0.0, 1.0));
+        assert_eq!(bounds(&scale(&log_axis(), &[0.0, -5.0])), (1.0, 10.0));
+    }
+
+    #[test]
+    fn test_scale_of_same_values() {
+        let axis = Axis {
+            begin_at_zero: false,
+            ..Axis::default()
+        };
+        let (min, max) = bounds(&scale(&axis, &[5.0, 5.0]));
+        assert!(min < 5.0 && 5.0 < max);
+        assert_eq!(bounds(&scale(&Axis::default(), &[0.0])), (-1.0, 1.0));
+        assert_eq!(bounds(&scale(&log_axis(), &[10.0])), (1.0, 100.0));
+    }
+
+    #[test]
+    fn test_scale_fits_values() {
+        let axis = Axis {
+            begin_at_zero: false,
+            ..Axis::default()
+        };
+        assert_eq!(bounds(&scale(&axis, &[12.0, 47.0])), (10.0, 50.0));
+        assert_eq!(bounds(&scale(&Axis::default(), &[12.0, 47.0])), (0.0, 50.0));
+        assert_eq!(bounds(&scale(&log_axis(), &[3.0, 250.0])), (1.0, 1000.0));
+    }
+
+    #[test]
+    fn test_scale_with_axis_bounds() {
+        let axis = Axis {
+            min: Some(-5.0),
+            max: Some(5.0),
+            ..Axis::default()
+        };
+        assert_eq!(bounds(&scale(&axis, &[100.0])), (-5.0, 5.0));
+    }
+
+    #[test]
+    fn test_scale_with_axis_bound_beyond_values() {
+        let axis = Axis {
+            min: Some(100.0),
+            ..Axis::default()
+        };
+        let (min, max) = bounds(&scale(&axis, &[1.0, 2.0]));
+        assert_eq!(min, 100.0);
+        assert!(max > min);
+        let axis = Axis {
+            max: Some(1.0),
+            begin_at_zero: false,
+            ..Axis::default()
+        };
+        let (min, max) = bounds(&scale(&axis, &[10.0, 20.0]));
+        assert_eq!(max, 1.0);
+        assert!(min < max);
+    }
+
+    #[test]
+    fn test_log_scale_ignores_non_positive_axis_bounds() {
+        let axis = Axis {
+            min: Some(0.0),
+            max: Some(-1.0),
+            ..log_axis()
+        };
+        assert_eq!(bounds(&scale(&axis, &[5.0, 50.0])), (1.0, 100.0));
+    }
+
+    #[test]
+    fn test_ticks_within_bounds() {
+        let linear = scale(&Axis::default(), &[12.0, 47.0]);
+        assert_eq!(linear.ticks(), vec![0.0, 10.0, 20.0, 30.0, 40.0, 50.0]);
+        let log = scale(&log_axis(), &[3.0, 250.0]);
+        let ticks = log.ticks();
+        assert_eq!(ticks.first(), Some(&1.0));
+        assert_eq!(ticks.last(), Some(&1000.0));
+        assert!(ticks.iter().all(|t| *t >= 1.0 && *t <= 1000.0));
+    }
+
+    #[test]
+    fn test_pixel() {
+        let linear = scale(&Axis::default(), &[12.0, 47.0]);
+        assert_eq!(linear.pixel(0.0), Some(100.0));
+        assert_eq!(linear.pixel(50.0), Some(0.0));
+        assert_eq!(linear.pixel(25.0), Some(50.0));
+        let log = scale(&log_axis(), &[3.0, 250.0]);
+        assert_eq!(log.pixel(0.0), None);
+        assert_eq!(log.pixel(1.0), Some(100.0));
+        assert_eq!(log.pixel(1000.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_baseline() {
+        assert_eq!(scale(&Axis::default(), &[-10.0, 10.0]).baseline(), 50.0);
+        assert_eq!(scale(&Axis::default(), &[10.0]).baseline(), 100.0);
+        let axis = Axis {
+            begin_at_zero: false,
+            ..Axis::default()
+        };
+        assert_eq!(scale(&axis, &[-20.0, -10.0]).baseline(), 0.0);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">&</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use chrono::{DateTime, Local};
-use std::time::SystemTime;
+use crate::config::{DateField, Dates, Timezone, XAxis};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const SHORT_SHA_LEN: usize = 7;
+const MERGE_PR_PREFIX: &str = "Merge pull request #";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CommitInfo {
@@ -12,26 +17,279 @@ pub struct CommitInfo {
     pub commit_date: SystemTime,
     pub message: String,
     pub view_url: String,
+    pub parents: Vec<String>,
 }
 
 impl CommitInfo {
     pub fn sha_short(&self) -> &str {
-        &self.sha[..SHORT_SHA_LEN]
+        self.sha.get(..SHORT_SHA_LEN).unwrap_or(&self.sha)
     }
 
     pub fn message_headline(&self) -> &str {
         self.message.split('\n').next().unwrap()
     }
 
-    pub fn author_date_str(&self) -> String {
-        DateTime::<Local>::from(self.author_date)
-            .format("%Y-%m-%d %H:%M")
-            .to_string()
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+
+    /// Returns the number of pull request merged by this commit, if it's a merge commit
+    /// created by merging a pull request on GitHub.
+    pub fn merged_pull_request(&self) -> Option<u32> {
+        if !self.is_merge() {
+            return None;
+        }
+        let rest = self.message_headline().strip_prefix(MERGE_PR_PREFIX)?;
+        rest.split_whitespace().next()?.parse().ok()
+    }
+
+    pub fn pull_request_url(&self) -> Option<String> {
+        let number = self.merged_pull_request()?;
+        let repo_url = &self.view_url[..self.view_url.rfind("/commit/")?];
+        Some(format!("{}/pull/{}", repo_url, number))
+    }
+
+    /// Returns the timestamp to place this commit on time scale by.
+    pub fn timestamp_millis(&self, x_axis: XAxis) -> Option<f64> {
+        let time = match x_axis {
+            XAxis::Commit => return None,
+            XAxis::AuthorDate => self.author_date,
+            XAxis::CommitDate => self.commit_date,
+        };
+        time.duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_millis() as f64)
+    }
+
+    /// Format the date of this commit as configured.
+    pub fn date_str(&self, dates: &Dates) -> String {
+        let time = DateTime::<Utc>::from(match dates.show {
+            DateField::Author => self.author_date,
+            DateField::Commit => self.commit_date,
+        });
+        let mut out = String::new();
+        // Formatting fails rather than panicking only when written this way, if the format
+        // contains an invalid specifier.
+        let result = match dates.timezone {
+            Timezone::Local => write!(out, "{}", time.with_timezone(&Local).format(&dates.format)),
+            Timezone::Utc => write!(out, "{}", time.format(&dates.format)),
+            Timezone::Fixed(offset) => {
+                write!(out, "{}", time.with_timezone(&offset).format(&dates.format))
+            }
+        };
+        if result.is_err() {
+            return time.to_rfc3339();
+        }
+        out
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct UserInfo {
     pub name: String,
     pub email: String,
 }
+
+/// Select commits on the first-parent history from the head, which is the first commit in
+/// the list. The history is followed as long as parents are in the list.
+pub fn first_parent_history(commits: Vec<CommitInfo>) -> Vec<CommitInfo> {
+    let mut next = match commits.first() {
+        Some(head) => head.sha.clone(),
+        None => return commits,
+    };
+    let mut by_sha: HashMap<_, _> = commits.into_iter().map(|c| (c.sha.clone(), c)).collect();
+    let mut history = Vec::new();
+    while let Some(commit) = by_sha.remove(&next) {
+        let parent = commit.parents.first().cloned();
+        history.push(commit);
+        match parent {
+            Some(parent) => next = parent,
+            None => break,
+        }
+    }
+    history
+}
+
+/// Attach tags to commits which are shown. Tags on a commit which isn't shown are carried to the
+/// nearest newer commit shown, as it's the first one containing the tagged changes.
+/// `listed` is the list of all commit shas in range, from the newest.
+pub fn carry_tags(
+    listed: &[String],
+    shown: &[CommitInfo],
+    mut tags: HashMap<String, Vec<String>>,
+) -> HashMap<String, Vec<String>> {
+    let shown: HashSet<_> = shown.iter().map(|c| c.sha.as_str()).collect();
+    let mut carried = HashMap::new();
+    let mut pending = Vec::new();
+    for sha in listed.iter().rev() {
+        if let Some(names) = tags.remove(sha) {
+            pending.extend(names);
+        }
+        if shown.contains(sha.as_str()) && !pending.is_empty() {
+            carried.insert(sha.clone(), std::mem::take(&mut pending));
+        }
+    }
+    carried
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn commit(sha: &str, parents: &[&str]) -> CommitInfo {
+        let user = UserInfo {
+            name: "user".to_string(),
+            email: "user@example.com".to_string(),
+        };
+        CommitInfo {
+            sha: sha.to_string(),
+            author: user.clone(),
+            author_date: SystemTime::UNIX_EPOCH,
+            committer: user,
+            commit_date: SystemTime::UNIX_EPOCH,
+            message: String::new(),
+            view_url: format!("https://github.com/owner/repo/commit/{}", sha),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn merge(sha: &str, message: &str) -> CommitInfo {
+        CommitInfo {
+            message: message.to_string(),
+            ..commit(sha, &["p1", "p2"])
+        }
+    }
+
+    fn shas(commits: &[CommitInfo]) -> Vec<&str> {
+        commits.iter().map(|c| c.sha.as_str()).collect()
+    }
+
+    #[test]
+    fn test_first_parent_history() {
+        let commits = vec![
+            commit("m", &["b", "x"]),
+            commit("x", &["a"]),
+            commit("b", &["a"]),
+            commit("a", &[]),
+        ];
+        assert_eq!(shas(&first_parent_history(commits)), ["m", "b", "a"]);
+    }
+
+    #[test]
+    fn test_first_parent_history_stops_out_of_list() {
+        let commits = vec![commit("c", &["b"]), commit("b", &["z"]), commit("a", &[])];
+        assert_eq!(shas(&first_parent_history(commits)), ["c", "b"]);
+        assert!(first_parent_history(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_merged_pull_request() {
+        let c = merge(
+            "m",
+            "Merge pull request #42 from owner/branch\n\nFix things",
+        );
+        assert!(c.is_merge());
+        assert_eq!(c.merged_pull_request(), Some(42));
+        assert_eq!(
+            c.pull_request_url().as_deref(),
+            Some("https://github.com/owner/repo/pull/42")
+        );
+    }
+
+    #[test]
+    fn test_not_merged_pull_request() {
+        let c = CommitInfo {
+            message: "Merge pull request #42 from owner/branch".to_string(),
+            ..commit("c", &["p"])
+        };
+        assert_eq!(c.merged_pull_request(), None);
+        let c = merge("m", "Merge branch 'main' into topic");
+        assert_eq!(c.merged_pull_request(), None);
+        let c = merge("m", "Merge pull request #abc from owner/branch");
+        assert_eq!(c.merged_pull_request(), None);
+        assert_eq!(c.pull_request_url(), None);
+    }
+
+    #[test]
+    fn test_pull_request_url_without_commit_path() {
+        let c = CommitInfo {
+            view_url: "https://example.com/m".to_string(),
+            ..merge("m", "Merge pull request #1 from owner/branch")
+        };
+        assert_eq!(c.pull_request_url(), None);
+    }
+
+    fn dates(show: DateField, format: &str, timezone: Timezone) -> Dates {
+        Dates {
+            show,
+            format: format.to_string(),
+            timezone,
+        }
+    }
+
+    fn dated_commit() -> CommitInfo {
+        let at = |s: &str| SystemTime::from(DateTime::parse_from_rfc3339(s).unwrap());
+        CommitInfo {
+            author_date: at("2020-01-02T03:04:05Z"),
+            commit_date: at("2020-02-03T04:05:06Z"),
+            ..commit("c", &[])
+        }
+    }
+
+    #[test]
+    fn test_date_str() {
+        let c = dated_commit();
+        let utc = |show| dates(show, "%Y-%m-%d %H:%M", Timezone::Utc);
+        assert_eq!(c.date_str(&utc(DateField::Author)), "2020-01-02 03:04");
+        assert_eq!(c.date_str(&utc(DateField::Commit)), "2020-02-03 04:05");
+        let local = dates(DateField::Author, "%Y-%m-%d %H:%M", Timezone::Local);
+        let expected = DateTime::<Local>::from(c.author_date)
+            .format("%Y-%m-%d %H:%M")
+            .to_string();
+        assert_eq!(c.date_str(&local), expected);
+    }
+
+    #[test]
+    fn test_date_str_in_fixed_timezone() {
+        let c = dated_commit();
+        let offset = FixedOffset::east_opt(9 * 3600).unwrap();
+        let jst = dates(DateField::Author, "%m/%d %H:%M %z", Timezone::Fixed(offset));
+        assert_eq!(c.date_str(&jst), "01/02 12:04 +0900");
+    }
+
+    #[test]
+    fn test_date_str_with_invalid_format() {
+        let c = dated_commit();
+        let invalid = dates(DateField::Author, "%Q", Timezone::Utc);
+        assert_eq!(c.date_str(&invalid), "2020-01-02T03:04:05+00:00");
+    }
+
+    #[test]
+    fn test_carry_tags() {
+        let listed: Vec<_> = ["e", "d", "c", "b", "a"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let shown = vec![commit("e", &["d"]), commit("c", &["b"])];
+        let mut tags = HashMap::new();
+        tags.insert("e".to_string(), vec!["v3".to_string()]);
+        tags.insert("d".to_string(), vec!["v2".to_string()]);
+        tags.insert("b".to_string(), vec!["v1".to_string()]);
+        tags.insert("a".to_string(), vec!["v0".to_string()]);
+        let carried = carry_tags(&listed, &shown, tags);
+        assert_eq!(carried.len(), 2);
+        assert_eq!(carried["c"], ["v0", "v1"]);
+        assert_eq!(carried["e"], ["v2", "v3"]);
+    }
+
+    #[test]
+    fn test_carry_tags_after_newest_shown() {
+        let listed: Vec<_> = ["b", "a"].iter().map(|s| s.to_string()).collect();
+        let shown = vec![commit("a", &[])];
+        let mut tags = HashMap::new();
+        tags.insert("b".to_string(), vec!["v1".to_string()]);
+        tags.insert("z".to_string(), vec!["v0".to_string()]);
+        assert!(carry_tags(&listed, &shown, tags).is_empty());
+    }
+}
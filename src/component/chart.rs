@@ -1,5 +1,7 @@
 use super::CommitViewData;
 use crate::chart::{self, Chart};
+use crate::config::{Axis, ChartType, Dates, Overlay, Series, Threshold, Unit, XAxis};
+use crate::hooks::Hooks;
 use log::*;
 use std::rc::Rc;
 use web_sys::Element;
@@ -9,43 +11,86 @@ use yew::prelude::*;
 /// A chart component which shows line chart of commits data
 pub struct ChartComponent<C: Chart + 'static> {
     props: Properties,
-    canvas_ref: NodeRef,
+    container_ref: NodeRef,
     chart: Option<C>,
+    /// Config which the current chart is created with.
+    config: Option<chart::Config>,
+    shown_data: Option<Rc<CommitViewData>>,
 }
 
 #[derive(Debug, Clone, Properties)]
 pub struct Properties {
     pub data: Option<Rc<CommitViewData>>,
     pub value_title: String,
+    pub thresholds: Vec<Threshold>,
+    pub overlays: Vec<Overlay>,
+    pub x_axis: XAxis,
+    pub dates: Dates,
+    pub axis: Axis,
+    pub unit: Option<Unit>,
+    pub series: Vec<Series>,
+    pub right_axis: Axis,
+    pub chart_type: ChartType,
+    pub span_gaps: bool,
+    pub hooks: Hooks,
 }
 
 impl<C: Chart> ChartComponent<C> {
+    fn chart_config(&self) -> chart::Config {
+        chart::Config {
+            title: self.props.value_title.clone(),
+            thresholds: self.props.thresholds.clone(),
+            overlays: self.props.overlays.clone(),
+            x_axis: self.props.x_axis,
+            dates: self.props.dates.clone(),
+            axis: self.props.axis.clone(),
+            unit: self.props.unit.clone(),
+            series: self.props.series.clone(),
+            right_axis: self.props.right_axis.clone(),
+            chart_type: self.props.chart_type,
+            span_gaps: self.props.span_gaps,
+            hooks: self.props.hooks.clone(),
+        }
+    }
+
     fn refresh_chart(&mut self) {
         let data = if let Some(data) = self.props.data.as_ref() {
-            data
+            Rc::clone(data)
         } else {
             debug!("Dataset is not ready");
             return;
         };
-
-        if let Some(chart) = self.chart.take() {
-            debug!("Destroying currently displaying chart");
-            drop(chart);
+        let config = self.chart_config();
+        let same_config = self.config.as_ref() == Some(&config);
+        let same_data = self
+            .shown_data
+            .as_ref()
+            .map(|shown| Rc::ptr_eq(shown, &data))
+            .unwrap_or(false);
+        if same_config && same_data {
+            return;
         }
 
-        debug!("Creating new chart with {} datapoints", data.commits.len());
-        let target = self
-            .canvas_ref
-            .cast::<Element>()
-            .expect("canvas_ref: !Element");
-        let chart = C::create(
-            target,
-            &chart::Config {
-                title: self.props.value_title.clone(),
-            },
-            data,
-        );
-        self.chart.replace(chart);
+        match self.chart.as_mut() {
+            Some(chart) if same_config => {
+                debug!("Updating chart with {} datapoints", data.commits.len());
+                chart.update(&data);
+            }
+            _ => {
+                if let Some(chart) = self.chart.take() {
+                    debug!("Destroying currently displaying chart");
+                    drop(chart);
+                }
+                debug!("Creating new chart with {} datapoints", data.commits.len());
+                let target = self
+                    .container_ref
+                    .cast::<Element>()
+                    .expect("container_ref: !Element");
+                self.chart.replace(C::create(target, &config, &data));
+                self.config.replace(config);
+            }
+        }
+        self.shown_data.replace(data);
     }
 }
 
@@ -56,8 +101,10 @@ impl<C: Chart> Component for ChartComponent<C> {
     fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
         Self {
             props,
-            canvas_ref: NodeRef::default(),
+            container_ref: NodeRef::default(),
             chart: None,
+            config: None,
+            shown_data: None,
         }
     }
 
@@ -72,7 +119,7 @@ impl<C: Chart> Component for ChartComponent<C> {
 
     fn view(&self) -> Html {
         html! {
-            <canvas ref=self.canvas_ref.clone() width="400" height="200"></canvas>
+            <div class="loupe-chart" ref=self.container_ref.clone()></div>
         }
     }
 
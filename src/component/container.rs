@@ -1,161 +1,294 @@
 use super::chart::{self, ChartComponent};
 use super::CommitViewData;
-use crate::api::{Api, CommitListRequest, CommitMetadataRequest};
 use crate::chart::Chart;
-use crate::commit::CommitInfo;
 use crate::component::table::{self, TableComponent};
-use crate::dataset::CommitDataSet;
+use crate::config::{Axis, ChartType, Dates, Overlay, Series, Threshold, Unit, XAxis};
+use crate::dataset::{CommitDataSet, Stage};
+use crate::format;
+use crate::hooks::Hooks;
 use crate::query::Query;
 use crate::range::Range;
+use crate::sampling;
+use crate::store::Failure;
 use log::*;
-use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 use std::rc::Rc;
 use yew::prelude::*;
 
 /// A container component to contain single unit of view
-pub struct ContainerComponent<C, A, M>
+pub struct ContainerComponent<C>
 where
     C: Chart + 'static,
-    A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
-    M: Api<CommitMetadataRequest, String> + 'static,
 {
     link: ComponentLink<Self>,
-    props: Properties<A, M>,
+    props: Properties,
     data: Option<Rc<CommitViewData>>,
+    /// Failure of running queries on the dataset.
+    query_failure: Option<Failure>,
+    /// Indexes of overlays turned off by user.
+    hidden_overlays: HashSet<usize>,
     phantom: PhantomData<C>,
 }
 
 /// Message types for `ContainerComponent`
 pub enum Msg {
-    /// Received target commits data
-    DataReady(CommitDataSet),
-    /// Error fetching data
-    DataFetchError(anyhow::Error),
+    /// Toggle visibility of the overlay at the index
+    ToggleOverlay(usize),
 }
 
-#[derive(Debug, Properties)]
-pub struct Apis<A: Api<CommitListRequest, Vec<CommitInfo>>, M: Api<CommitMetadataRequest, String>> {
-    pub commits: Rc<RefCell<A>>,
-    pub metadata: Rc<RefCell<M>>,
+#[derive(Debug, Clone, Properties)]
+pub struct Properties {
+    pub range: Range,
+    /// Dataset of the file from the data store, which might be partial while loading.
+    pub dataset: Option<Rc<CommitDataSet>>,
+    /// Failure of fetching the dataset.
+    pub failure: Option<Failure>,
+    /// Called to fetch the dataset again after failure.
+    pub on_retry: Callback<()>,
+    pub hooks: Hooks,
+    pub value_title: String,
+    pub query: String,
+    pub thresholds: Vec<Threshold>,
+    pub overlays: Vec<Overlay>,
+    pub x_axis: XAxis,
+    pub dates: Dates,
+    /// Show the column of tags in the table, which are listed only if configured.
+    pub show_tags: bool,
+    pub axis: Axis,
+    pub unit: Option<Unit>,
+    pub series: Vec<Series>,
+    pub right_axis: Axis,
+    pub chart_type: ChartType,
+    pub span_gaps: bool,
 }
 
-impl<A: Api<CommitListRequest, Vec<CommitInfo>>, M: Api<CommitMetadataRequest, String>> Clone
-    for Apis<A, M>
-{
-    fn clone(&self) -> Self {
-        Self {
-            commits: Rc::clone(&self.commits),
-            metadata: Rc::clone(&self.metadata),
-        }
+impl Properties {
+    /// Returns true if view data made from the properties could differ from the other's.
+    fn view_data_differs(&self, other: &Self) -> bool {
+        let same_dataset = match (&self.dataset, &other.dataset) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        !same_dataset
+            || self.query != other.query
+            || self.series != other.series
+            || self.range.sampling != other.range.sampling
+            || self.range.samples != other.range.samples
     }
 }
 
-#[derive(Debug, Properties)]
-pub struct Properties<
-    A: Api<CommitListRequest, Vec<CommitInfo>>,
-    M: Api<CommitMetadataRequest, String>,
-> {
-    pub repo: String,
-    pub range: Range,
-    pub file: String,
-    pub value_title: String,
-    pub query: String,
-    pub apis: Apis<A, M>,
-}
+impl<C: Chart> ContainerComponent<C> {
+    /// Make view data from the dataset by running queries on it.
+    fn make_view_data(&mut self) {
+        let dataset = match self.props.dataset.as_ref() {
+            Some(dataset) => dataset,
+            None => return,
+        };
+        match Self::run_queries(&self.props, dataset) {
+            Ok(view_data) => {
+                let range = &self.props.range;
+                let view_data =
+                    sampling::downsample(view_data, range.sampling, range.samples as usize);
+                if dataset.is_complete() {
+                    let props = &self.props;
+                    props
+                        .hooks
+                        .data_loaded(&props.value_title, &props.series, &view_data);
+                }
+                self.data.replace(Rc::new(view_data));
+                self.query_failure = None;
+            }
+            Err(message) => {
+                error!(
+                    "Could not make view data from fetched metadata: {}",
+                    message
+                );
+                self.data = None;
+                self.query_failure = Some(Failure {
+                    stage: Stage::Query,
+                    message,
+                });
+            }
+        }
+    }
 
-impl<A: Api<CommitListRequest, Vec<CommitInfo>>, M: Api<CommitMetadataRequest, String>> Clone
-    for Properties<A, M>
-{
-    fn clone(&self) -> Self {
-        Self {
-            repo: self.repo.clone(),
-            range: self.range.clone(),
-            file: self.file.clone(),
-            value_title: self.value_title.clone(),
-            query: self.query.clone(),
-            apis: self.apis.clone(),
+    fn run_queries(props: &Properties, dataset: &CommitDataSet) -> Result<CommitViewData, String> {
+        let compile = |query: &str| {
+            Query::new(query)
+                .map_err(|e| format!("cannot instantiate jmespath query '{}': {}", query, e))
+        };
+        let query = compile(&props.query)?;
+        let series_queries = props
+            .series
+            .iter()
+            .map(|s| compile(&s.query))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CommitViewData::from_dataset(
+            dataset,
+            &query,
+            &series_queries,
+        ))
+    }
+
+    fn failure(&self) -> Option<&Failure> {
+        self.props.failure.as_ref().or(self.query_failure.as_ref())
+    }
+
+    /// Returns true if there's no value to show in the complete dataset.
+    fn is_empty(&self) -> bool {
+        let complete = self
+            .props
+            .dataset
+            .as_ref()
+            .map(|ds| ds.is_complete())
+            .unwrap_or(false);
+        let no_value = self
+            .data
+            .as_ref()
+            .map(|data| data.metadata.is_empty() && data.series.iter().all(HashMap::is_empty))
+            .unwrap_or(true);
+        complete && no_value
+    }
+
+    /// Show the state of loading data, or why nothing is shown.
+    fn view_status(&self) -> Html {
+        if let Some(failure) = self.failure() {
+            return html! {
+                <div class="loupe-status loupe-error">
+                  { format!("Failed in {}: {}", failure.stage, failure.message) }
+                  <button type="button" class="loupe-button loupe-retry"
+                          onclick=self.props.on_retry.reform(|_| ())>{ "Retry" }</button>
+                </div>
+            };
+        }
+        match self.props.dataset.as_ref() {
+            None => html! {
+                <div class="loupe-status loupe-progress">
+                  <span class="loupe-spinner"></span>
+                  { "Listing commits" }
+                </div>
+            },
+            Some(dataset) if !dataset.is_complete() => html! {
+                <div class="loupe-status loupe-progress">
+                  <span class="loupe-spinner"></span>
+                  <progress max=dataset.total value=dataset.loaded></progress>
+                  { format!("Loading {}/{}", dataset.loaded, dataset.total) }
+                </div>
+            },
+            Some(_) if self.is_empty() => html! {
+                <div class="loupe-status loupe-empty">{ "No data in range" }</div>
+            },
+            Some(dataset) if dataset.truncated => html! {
+                <div class="loupe-status loupe-truncated">
+                  { "Only the latest commits in range are shown, as there are too many to list" }
+                </div>
+            },
+            _ => html! {},
         }
     }
-}
 
-impl<
-        C: Chart,
-        A: Api<CommitListRequest, Vec<CommitInfo>>,
-        M: Api<CommitMetadataRequest, String>,
-    > ContainerComponent<C, A, M>
-{
-    fn fetch_view_data(&self) {
-        let cb = self.link.callback(|resp| match resp {
-            Ok(dataset) => Msg::DataReady(dataset),
-            Err(e) => Msg::DataFetchError(anyhow::Error::new(e)),
+    fn table_columns(&self) -> Vec<table::Column> {
+        let props = &self.props;
+        let formatters = format::series_formatters(
+            props.unit.as_ref(),
+            &props.axis,
+            &props.series,
+            &props.right_axis,
+        );
+        let titles =
+            std::iter::once(&props.value_title).chain(props.series.iter().map(|s| &s.title));
+        titles
+            .zip(formatters)
+            .map(|(title, formatter)| table::Column {
+                title: title.clone(),
+                formatter,
+            })
+            .collect()
+    }
+
+    fn view_overlay_toggles(&self) -> Html {
+        let toggles = self.props.overlays.iter().enumerate().map(|(i, overlay)| {
+            html! {
+                <label class="loupe-overlay-toggle">
+                  <input type="checkbox" checked=!self.hidden_overlays.contains(&i)
+                         onclick=self.link.callback(move |_| Msg::ToggleOverlay(i))/>
+                  { overlay.label() }
+                </label>
+            }
         });
+        html! {
+            <div class="loupe-overlay-toggles">
+              { for toggles }
+            </div>
+        }
+    }
 
-        CommitDataSet::collect_range(
-            Rc::clone(&self.props.apis.commits),
-            Rc::clone(&self.props.apis.metadata),
-            &self.props.repo,
-            &self.props.file,
-            self.props.range.clone(),
-            move |resp| cb.emit(resp),
-        );
+    fn view_thresholds_summary(&self) -> Html {
+        let data = match self.data.as_ref() {
+            Some(data) if !self.props.thresholds.is_empty() => data,
+            _ => return html! {},
+        };
+        let summaries = self.props.thresholds.iter().map(|threshold| {
+            let violations = data
+                .metadata
+                .values()
+                .filter(|v| threshold.is_violated_by(**v))
+                .count();
+            let class = if violations > 0 {
+                "loupe-threshold loupe-threshold-violated"
+            } else {
+                "loupe-threshold"
+            };
+            html! {
+                <li class=class>
+                  { format!("{}: {} of {} datapoints violated", threshold.label(), violations, data.metadata.len()) }
+                </li>
+            }
+        });
+        html! {
+            <ul class="loupe-thresholds-summary">
+              { for summaries }
+            </ul>
+        }
     }
 }
 
-impl<
-        C: Chart,
-        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
-        M: Api<CommitMetadataRequest, String> + 'static,
-    > Component for ContainerComponent<C, A, M>
-{
+impl<C: Chart> Component for ContainerComponent<C> {
     type Message = Msg;
-    type Properties = Properties<A, M>;
+    type Properties = Properties;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let this = Self {
+        let mut this = Self {
             link,
             props,
             data: None,
+            query_failure: None,
+            hidden_overlays: HashSet::new(),
             phantom: PhantomData,
         };
-        this.fetch_view_data();
+        this.make_view_data();
         this
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let differs = props.view_data_differs(&self.props);
         self.props = props;
-        self.fetch_view_data();
-        // fetch_view_data() will trigger re-rendering.
-        false
+        if differs {
+            self.make_view_data();
+        }
+        true
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            Msg::DataReady(dataset) => {
-                let query = match Query::new(&self.props.query) {
-                    Ok(q) => q,
-                    Err(e) => {
-                        error!(
-                            "cannot instantiate jmespath query '{}': {}",
-                            self.props.query, e
-                        );
-                        return false;
-                    }
-                };
-
-                match CommitViewData::from_dataset(dataset, &query) {
-                    Ok(view_data) => {
-                        self.data.replace(Rc::new(view_data));
-                    }
-                    Err(e) => error!("Could not make view data from fetched metadata: {}", e),
+            Msg::ToggleOverlay(index) => {
+                if !self.hidden_overlays.remove(&index) {
+                    self.hidden_overlays.insert(index);
                 }
-
                 true
             }
-            Msg::DataFetchError(e) => {
-                error!("Error in fetching data: {}", e);
-                false
-            }
         }
     }
 
@@ -163,15 +296,45 @@ impl<
         let chart_props = chart::Properties {
             data: self.data.as_ref().map(Rc::clone),
             value_title: self.props.value_title.clone(),
+            thresholds: self.props.thresholds.clone(),
+            overlays: self
+                .props
+                .overlays
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !self.hidden_overlays.contains(i))
+                .map(|(_, o)| o.clone())
+                .collect(),
+            x_axis: self.props.x_axis,
+            dates: self.props.dates.clone(),
+            axis: self.props.axis.clone(),
+            unit: self.props.unit.clone(),
+            series: self.props.series.clone(),
+            right_axis: self.props.right_axis.clone(),
+            chart_type: self.props.chart_type,
+            span_gaps: self.props.span_gaps,
+            hooks: self.props.hooks.clone(),
         };
         let table_props = table::Properties {
-            value_title: self.props.value_title.clone(),
+            columns: self.table_columns(),
             data: self.data.as_ref().map(Rc::clone),
+            dates: self.props.dates.clone(),
+            show_tags: self.props.show_tags,
         };
 
+        if self.failure().is_some() || self.is_empty() {
+            return html! {
+                <div class="loupe-container">
+                  { self.view_status() }
+                </div>
+            };
+        }
         html! {
             <div class="loupe-container">
+              { self.view_status() }
               <ChartComponent<C> with chart_props />
+              { self.view_overlay_toggles() }
+              { self.view_thresholds_summary() }
               <TableComponent with table_props />
             </div>
         }
@@ -1,20 +1,13 @@
 use super::container::{self, ContainerComponent};
-use crate::api::github::GitHubApi;
-use crate::api::static_metadata::StaticMetadataApi;
-use crate::api::{CommitListRequest, CommitMetadataRequest};
-use crate::cache::ApiCache;
 use crate::chart::Chart;
-use crate::commit::CommitInfo;
 use crate::config::Config;
-use crate::range::Range;
-use std::cell::RefCell;
+use crate::hooks::Hooks;
+use crate::range::{self, Range, RangeSpec};
+use crate::store::{self, DataStore};
+use chrono::{DateTime, Utc};
 use std::marker::PhantomData;
-use std::rc::Rc;
 use yew::prelude::*;
 
-type CachedCommitsApi = ApiCache<CommitListRequest, Vec<CommitInfo>, GitHubApi>;
-type CachedMetadataApi = ApiCache<CommitMetadataRequest, String, StaticMetadataApi>;
-
 /// The main component
 pub struct LoupeComponent<C>
 where
@@ -23,7 +16,8 @@ where
     link: ComponentLink<Self>,
     props: Properties,
     range: Range,
-    apis: container::Apis<CachedCommitsApi, CachedMetadataApi>,
+    store: DataStore,
+    destroyed: bool,
     _phantom: PhantomData<C>,
 }
 
@@ -31,88 +25,238 @@ where
 pub enum Msg {
     ZoomIn,
     ZoomOut,
+    SetSince(Option<DateTime<Utc>>),
+    SetUntil(Option<DateTime<Utc>>),
+    SetBase(Option<String>),
+    SetHead(Option<String>),
+    /// Event from the data store
+    Store(store::Event),
+    /// Fetch data again after failure
+    Retry,
+    /// Replace the config and hooks, resetting the range
+    SetConfig(Box<Config>, Hooks),
+    /// Replace the range with the one made from the config and the given bounds
+    SetRange(RangeSpec),
+    /// Fetch data again dropping everything cached, to show commits pushed since then
+    Refresh,
+    /// Unmount everything, after which no message takes effect
+    Destroy,
 }
 
 #[derive(Debug, Clone, Properties)]
 pub struct Properties {
     pub config: Config,
+    pub hooks: Hooks,
 }
 
 impl<C: Chart> LoupeComponent<C> {
+    fn initial_range(config: &Config) -> Range {
+        let mut range = Range::new(config.branch.clone(), 50, 50);
+        range.base = config.base.clone();
+        range.sampling = config.sampling;
+        range.skip_missing = config.skip_missing;
+        range.first_parent = config.first_parent;
+        range
+    }
+
+    /// Fetch data of all charts in the current range.
+    fn fetch(&mut self) {
+        let files = self.props.config.data.iter().map(|d| d.file.clone());
+        self.store
+            .fetch(&self.range, files, self.link.callback(Msg::Store));
+    }
+
+    /// Fetch data of the range which has been changed, telling the hook about it.
+    fn change_range(&mut self) {
+        self.props.hooks.range_change(&self.range);
+        self.fetch();
+    }
+
     fn view_containers(&self) -> Vec<Html> {
         let cfg = &self.props.config;
 
         let mut htmls = Vec::with_capacity(cfg.data.len());
         for data in &cfg.data {
             let props = container::Properties {
-                repo: cfg.repo.clone(),
                 range: self.range.clone(),
-                file: data.file.clone(),
+                dataset: self.store.dataset(&data.file),
+                failure: self.store.failure(&data.file),
+                on_retry: self.link.callback(|_| Msg::Retry),
+                hooks: self.props.hooks.clone(),
                 value_title: data.title.clone(),
                 query: data.query.clone(),
-                apis: self.apis.clone(),
+                thresholds: data.thresholds.clone(),
+                overlays: data.overlays.clone(),
+                x_axis: cfg.x_axis,
+                dates: cfg.dates.clone(),
+                show_tags: cfg.tags.is_some(),
+                axis: data.axis.clone(),
+                unit: data.unit.clone(),
+                series: data.series.clone(),
+                right_axis: data.right_axis.clone(),
+                chart_type: data.chart_type,
+                span_gaps: data.span_gaps,
             };
             htmls.push(html! {
-                <ContainerComponent<C, _, _> with props />
+                <ContainerComponent<C> with props />
             });
         }
         htmls
     }
+
+    fn view_range_panel(&self) -> Html {
+        if !self.props.config.components.show_range {
+            return html! {};
+        }
+        let date_input = |change| match change {
+            ChangeData::Value(v) => Some(v),
+            _ => None,
+        };
+        let ref_input = |change| match change {
+            ChangeData::Value(v) if !v.trim().is_empty() => Some(v.trim().to_owned()),
+            _ => None,
+        };
+        html! {
+            <div class="loupe-range-panel">
+              <label>{ "Since " }
+                <input type="date" class="loupe-range-since"
+                       value=range::format_date_input(self.range.since)
+                       onchange=self.link.callback(move |c| Msg::SetSince(
+                           date_input(c).and_then(|v| range::parse_date_input(&v, false))))/>
+              </label>
+              <label>{ " Until " }
+                <input type="date" class="loupe-range-until"
+                       value=range::format_date_input(self.range.until)
+                       onchange=self.link.callback(move |c| Msg::SetUntil(
+                           date_input(c).and_then(|v| range::parse_date_input(&v, true))))/>
+              </label>
+              <label>{ " Base " }
+                <input type="text" class="loupe-range-base" placeholder="e.g. v1.2.0"
+                       value=self.range.base.clone().unwrap_or_default()
+                       onchange=self.link.callback(move |c| Msg::SetBase(ref_input(c)))/>
+              </label>
+              <label>{ " Head " }
+                <input type="text" class="loupe-range-head" placeholder=self.range.head()
+                       value=self.range.from.clone().unwrap_or_default()
+                       onchange=self.link.callback(move |c| Msg::SetHead(ref_input(c)))/>
+              </label>
+            </div>
+        }
+    }
 }
 
 impl<C: Chart> Component for LoupeComponent<C> {
     type Message = Msg;
     type Properties = Properties;
 
-    fn create(mut props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let range = Range::new(props.config.branch.take(), 50, 50);
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let range = Self::initial_range(&props.config);
+        let store = DataStore::new(&props.config);
 
-        let mut _gh_api = GitHubApi::new();
-        let gh_api = ApiCache::new(_gh_api);
-        let _meta_api = StaticMetadataApi::new(props.config.data_url.clone());
-        let meta_api = ApiCache::new(_meta_api);
-        let apis = container::Apis {
-            commits: Rc::new(RefCell::new(gh_api)),
-            metadata: Rc::new(RefCell::new(meta_api)),
-        };
-
-        Self {
+        let mut this = Self {
             link,
             props,
             range,
-            apis,
+            store,
+            destroyed: false,
             _phantom: PhantomData,
-        }
+        };
+        this.fetch();
+        this
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.store.reset(&props.config);
         self.props = props;
+        self.fetch();
         true
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         use Msg::*;
+        if self.destroyed {
+            return false;
+        }
         match msg {
+            ZoomIn | ZoomOut if !self.range.can_zoom() => false,
             ZoomIn => {
                 self.range.zoom(0.5);
+                self.change_range();
                 true
             }
             ZoomOut => {
                 self.range.zoom(2.0);
+                self.change_range();
+                true
+            }
+            SetSince(since) => {
+                self.range.since = since;
+                self.change_range();
+                true
+            }
+            SetUntil(until) => {
+                self.range.until = until;
+                self.change_range();
+                true
+            }
+            SetBase(base) => {
+                self.range.base = base;
+                self.change_range();
+                true
+            }
+            SetHead(head) => {
+                self.range.from = head;
+                self.change_range();
+                true
+            }
+            Store(event) => self.store.receive(event),
+            Retry => {
+                self.fetch();
+                true
+            }
+            SetConfig(config, hooks) => {
+                self.range = Self::initial_range(&config);
+                self.store.reset(&config);
+                self.props.config = *config;
+                self.props.hooks = hooks;
+                self.change_range();
+                true
+            }
+            SetRange(spec) => {
+                self.range = Self::initial_range(&self.props.config);
+                self.range.apply(spec);
+                self.change_range();
+                true
+            }
+            Refresh => {
+                self.store.reset(&self.props.config);
+                self.fetch();
+                true
+            }
+            Destroy => {
+                // Rendering nothing drops charts, which release what they have created.
+                self.destroyed = true;
                 true
             }
         }
     }
 
     fn view(&self) -> Html {
+        if self.destroyed {
+            return html! {};
+        }
+        let zoom_disabled = !self.range.can_zoom();
         html! {
             <div class="loupe-root">
               <div class="loupe-ctl-container">
                 <button type="button" class="loupe-button loupe-ctl-zoom-in"
+                        disabled=zoom_disabled
                         onclick=self.link.callback(|_| Msg::ZoomIn)>{ "+ Zoom In" }</button>
                 <button type="button" class="loupe-button loupe-ctl-zoom-out"
+                        disabled=zoom_disabled
                         onclick=self.link.callback(|_| Msg::ZoomOut)>{ "- Zoom Out" }</button>
               </div>
+              { self.view_range_panel() }
               <div class="loupe-panels">
                 { for self.view_containers() }
               </div>
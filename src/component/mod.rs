@@ -4,29 +4,87 @@ pub mod loupe;
 pub mod table;
 
 use crate::commit::CommitInfo;
-use crate::dataset::CommitDataSet;
-use crate::query::{self, Query};
+use crate::dataset::{CommitDataSet, Status};
+use crate::query::Query;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CommitViewData {
     pub commits: Vec<CommitInfo>,
     pub metadata: HashMap<String, f64>,
+    /// Values of each extra series keyed by sha, in the configured order.
+    pub series: Vec<HashMap<String, f64>>,
+    /// Commits aggregated into the datapoint of the commit, keyed by sha.
+    pub spans: HashMap<String, CommitSpan>,
+    /// Names of tags to annotate on each commit.
+    pub tags: HashMap<String, Vec<String>>,
+    /// Status of each commit's datapoint, which is absent while its metadata is being fetched.
+    pub statuses: HashMap<String, Status>,
+}
+
+/// Range of consecutive commits which are aggregated into a datapoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitSpan {
+    pub newest: CommitInfo,
+    pub oldest: CommitInfo,
+    pub count: usize,
+}
+
+impl CommitSpan {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} commits: {}..{}",
+            self.count,
+            self.oldest.sha_short(),
+            self.newest.sha_short()
+        )
+    }
 }
 
 impl CommitViewData {
-    pub fn from_dataset(ds: CommitDataSet, query: &Query) -> Result<Self, query::Error> {
+    /// Make view data by running queries on metadata of each commit. Failure of a query is
+    /// recorded in the status of the commit without affecting others.
+    pub fn from_dataset(ds: &CommitDataSet, query: &Query, series: &[Query]) -> Self {
         let mut meta_vals = HashMap::new();
+        let mut series_vals = vec![HashMap::new(); series.len()];
+        let mut statuses = ds.failures.clone();
         for c in &ds.commits {
-            if let Some(json) = ds.metadata.get(&c.sha) {
-                if let Some(value) = query.extract_value(json)? {
-                    meta_vals.insert(c.sha.clone(), value);
+            let var = match ds.metadata.get(&c.sha) {
+                Some(var) => var,
+                None => continue,
+            };
+            let mut status = Status::Ok;
+            let queries = std::iter::once((query, &mut meta_vals))
+                .chain(series.iter().zip(series_vals.iter_mut()));
+            for (query, values) in queries {
+                match query.extract_value(var) {
+                    Ok(value) => {
+                        values.insert(c.sha.clone(), value);
+                    }
+                    Err(e) => {
+                        if status == Status::Ok {
+                            status = Status::NonNumeric(e.to_string());
+                        }
+                    }
                 }
             }
+            statuses.insert(c.sha.clone(), status);
         }
-        Ok(Self {
-            commits: ds.commits,
+        Self {
+            commits: ds.commits.clone(),
             metadata: meta_vals,
-        })
+            series: series_vals,
+            spans: HashMap::new(),
+            tags: ds.tags.clone(),
+            statuses,
+        }
+    }
+
+    /// Returns values of the main value at index 0, or of the extra series from index 1.
+    pub fn values(&self, index: usize) -> Option<&HashMap<String, f64>> {
+        match index {
+            0 => Some(&self.metadata),
+            i => self.series.get(i - 1),
+        }
     }
 }
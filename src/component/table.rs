@@ -1,5 +1,7 @@
 use super::CommitViewData;
 use crate::commit::CommitInfo;
+use crate::config::{DateField, Dates};
+use crate::format::ValueFormatter;
 use std::rc::Rc;
 use yew::prelude::*;
 
@@ -10,23 +12,56 @@ pub struct TableComponent {
 
 #[derive(Debug, Clone, Properties)]
 pub struct Properties {
-    pub value_title: String,
+    /// Columns of the main value followed by extra series.
+    pub columns: Vec<Column>,
     pub data: Option<Rc<CommitViewData>>,
+    pub dates: Dates,
+    pub show_tags: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub title: String,
+    pub formatter: ValueFormatter,
 }
 
 impl TableComponent {
-    fn view_commit_table_entry(data: &CommitViewData, commit: &CommitInfo) -> Html {
-        let throughput = data
-            .metadata
-            .get(&commit.sha)
-            .map(|v| format!("{:.2}", v))
-            .unwrap_or_else(|| "N/A".to_string());
+    fn view_commit_table_entry(&self, data: &CommitViewData, commit: &CommitInfo) -> Html {
+        let status = data.statuses.get(&commit.sha);
+        let values = self.props.columns.iter().enumerate().map(|(i, column)| {
+            let value = data.values(i).and_then(|values| values.get(&commit.sha));
+            match (value, status) {
+                (Some(value), _) => html! { <th>{ column.formatter.format(*value) }</th> },
+                (None, Some(status)) => html! {
+                    <th class="loupe-value-missing" title=status.to_string()>{ status.label() }</th>
+                },
+                (None, None) => html! { <th class="loupe-value-missing">{ "..." }</th> },
+            }
+        });
+        let tags = if self.props.show_tags {
+            let names = data
+                .tags
+                .get(&commit.sha)
+                .map(|names| names.join(", "))
+                .unwrap_or_default();
+            html! { <th>{ names }</th> }
+        } else {
+            html! {}
+        };
+        let merged_pr = match (commit.merged_pull_request(), commit.pull_request_url()) {
+            (Some(number), Some(url)) => html! {
+                <a class="loupe-merged-pr" href=url>{ format!("#{}", number) }</a>
+            },
+            _ => html! {},
+        };
         html! {
           <tr>
             <th><a href=commit.view_url.clone()>{ commit.sha_short() }</a></th>
-            <th>{ commit.author_date_str() }</th>
-            <th>{ commit.message_headline() }</th>
-            <th>{ throughput }</th>
+            <th>{ commit.date_str(&self.props.dates) }</th>
+            <th>{ &commit.committer.name }</th>
+            { tags }
+            <th>{ merged_pr }{ commit.message_headline() }</th>
+            { for values }
           </tr>
         }
     }
@@ -35,7 +70,7 @@ impl TableComponent {
         if let Some(data) = self.props.data.as_ref() {
             let mut htmls = Vec::with_capacity(data.commits.len());
             for commit in &data.commits {
-                htmls.push(Self::view_commit_table_entry(&data, commit));
+                htmls.push(self.view_commit_table_entry(data, commit));
             }
             htmls
         } else {
@@ -62,14 +97,20 @@ impl Component for TableComponent {
     }
 
     fn view(&self) -> Html {
+        let date_title = match self.props.dates.show {
+            DateField::Author => "Authored",
+            DateField::Commit => "Committed",
+        };
         html! {
             <table class="loupe-commits-table">
             <thead>
               <tr>
                 <th>{ "Commit" }</th>
-                <th>{ "Timestamp" }</th>
+                <th>{ date_title }</th>
+                <th>{ "Committer" }</th>
+                { if self.props.show_tags { html! { <th>{ "Tags" }</th> } } else { html! {} } }
                 <th>{ "Subject" }</th>
-                <th>{ &self.props.value_title }</th>
+                { for self.props.columns.iter().map(|c| html! { <th>{ &c.title }</th> }) }
               </tr>
             </thead>
             <tbody>
@@ -1,24 +1,771 @@
-use serde::{Deserialize, Serialize};
+use crate::query::Query;
+use chrono::FixedOffset;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use url::Url;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub on: String,
     pub repo: String,
     pub branch: Option<String>,
+    /// Show commits between this ref and `branch` instead of the latest ones when set.
+    pub base: Option<String>,
     pub data_url: String,
+    #[serde(default)]
+    pub sampling: Sampling,
+    /// Sample only commits having data, so that every sample makes a datapoint.
+    #[serde(default)]
+    pub skip_missing: bool,
+    /// Show only commits on the first-parent history of the branch.
+    #[serde(default)]
+    pub first_parent: bool,
+    /// Where to list tags to annotate on charts from.
+    pub tags: Option<TagSource>,
+    #[serde(default)]
+    pub x_axis: XAxis,
+    #[serde(default)]
+    pub renderer: Renderer,
+    /// How to show dates of commits in tooltips and the table.
+    #[serde(default)]
+    pub dates: Dates,
     pub components: Components,
     pub data: Vec<Data>,
 }
 
+impl Config {
+    /// Parse the config, collecting fields unknown to it instead of failing on them so that the
+    /// rest can still be validated. Returns the config and paths of the unknown fields.
+    pub fn parse<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<(Self, Vec<String>), D::Error> {
+        let mut unknown = Vec::new();
+        let config = serde_ignored::deserialize(deserializer, |path| {
+            let mut at = String::new();
+            field_path(&path, &mut at);
+            unknown.push(at);
+        })?;
+        Ok((config, unknown))
+    }
+
+    /// Check the config for problems which would make charts fail only after fetching data.
+    /// Returns descriptions of all problems found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if !is_valid_repo(&self.repo) {
+            problems.push(format!(
+                "repo: '{}' is not in the form of 'owner/name'",
+                self.repo
+            ));
+        }
+        if let Some(problem) = data_url_problem(&self.data_url) {
+            problems.push(format!("data_url: '{}' {}", self.data_url, problem));
+        }
+
+        let mut titles = HashSet::new();
+        for (i, data) in self.data.iter().enumerate() {
+            let at = format!("data[{}] ({})", i, data.title);
+            if !titles.insert(&data.title) {
+                problems.push(format!("{}: duplicate title '{}'", at, data.title));
+            }
+            if let Err(e) = Query::new(&data.query) {
+                problems.push(format!("{}.query: '{}' is invalid: {}", at, data.query, e));
+            }
+            for (name, axis) in &[("axis", &data.axis), ("right_axis", &data.right_axis)] {
+                let axis_problems = axis
+                    .bounds_problem()
+                    .into_iter()
+                    .chain(axis.format_problem());
+                for problem in axis_problems {
+                    problems.push(format!("{}.{}: {}", at, name, problem));
+                }
+            }
+            if data.unit.is_some() && data.axis.sets_format() {
+                problems.push(format!(
+                    "{}.unit: can't be set along with axis.unit or axis.format",
+                    at
+                ));
+            }
+
+            let mut series_titles = HashSet::new();
+            series_titles.insert(&data.title);
+            for (j, series) in data.series.iter().enumerate() {
+                let at = format!("{}.series[{}] ({})", at, j, series.title);
+                if !series_titles.insert(&series.title) {
+                    problems.push(format!("{}: duplicate title '{}'", at, series.title));
+                }
+                if let Err(e) = Query::new(&series.query) {
+                    problems.push(format!(
+                        "{}.query: '{}' is invalid: {}",
+                        at, series.query, e
+                    ));
+                }
+                let (name, axis) = match series.y_axis {
+                    AxisPosition::Left => ("axis", &data.axis),
+                    AxisPosition::Right => ("right_axis", &data.right_axis),
+                };
+                if series.unit.is_some() && axis.sets_format() {
+                    problems.push(format!(
+                        "{}.unit: can't be set along with {name}.unit or {name}.format",
+                        at,
+                        name = name
+                    ));
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// Write the path to a field in the form of `data[0].axis.unit`.
+fn field_path(path: &serde_ignored::Path, at: &mut String) {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => {}
+        Path::Seq { parent, index } => {
+            field_path(parent, at);
+            at.push_str(&format!("[{}]", index));
+        }
+        Path::Map { parent, key } => {
+            field_path(parent, at);
+            if !at.is_empty() {
+                at.push('.');
+            }
+            at.push_str(key);
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_path(parent, at),
+    }
+}
+
+/// Returns the problem of `data_url` if any. Paths of data files are appended to it, and it might
+/// be relative to the page, e.g., `./commit-data`.
+fn data_url_problem(data_url: &str) -> Option<String> {
+    if data_url.is_empty() {
+        return Some("is empty".to_owned());
+    }
+    if data_url
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Some("contains whitespace".to_owned());
+    }
+    let url = match Url::parse(data_url) {
+        Ok(url) if url.scheme() != "http" && url.scheme() != "https" => {
+            return Some(format!(
+                "has scheme '{}' but not http or https",
+                url.scheme()
+            ));
+        }
+        Ok(url) => url,
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            let page = Url::parse("https://localhost/").expect("valid base url");
+            match page.join(data_url) {
+                Ok(url) => url,
+                Err(e) => return Some(format!("is not a URL: {}", e)),
+            }
+        }
+        Err(e) => return Some(format!("is not a URL: {}", e)),
+    };
+    if url.query().is_some() || url.fragment().is_some() {
+        return Some("has a query or fragment, which paths of data files can't follow".to_owned());
+    }
+    None
+}
+
+/// Returns true if the repo is in the form of `owner/name` as GitHub accepts.
+fn is_valid_repo(repo: &str) -> bool {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    };
+    match repo.split_once('/') {
+        Some((owner, name)) => valid_part(owner) && valid_part(name),
+        None => false,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Components {
     pub show_table: bool,
     pub show_range: bool,
 }
 
+/// Strategy to select datapoints to show from commits in range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampling {
+    /// Pick every Nth commit.
+    #[default]
+    Stride,
+    /// Split commits into buckets and show mean of each bucket.
+    Mean,
+    /// Split commits into buckets and show minimum of each bucket.
+    Min,
+    /// Split commits into buckets and show maximum of each bucket.
+    Max,
+    /// Pick commits preserving the shape of the chart by Largest-Triangle-Three-Buckets.
+    Lttb,
+}
+
+/// What to position datapoints on X axis by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum XAxis {
+    /// Place commits at even intervals.
+    #[default]
+    Commit,
+    /// Place commits on time scale by author date.
+    AuthorDate,
+    /// Place commits on time scale by commit date.
+    CommitDate,
+}
+
+/// Implementation to render charts by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Renderer {
+    /// Chart.js, which is available only when built with `chartjs` feature.
+    #[default]
+    #[serde(rename = "chartjs")]
+    ChartJs,
+    /// SVG rendered without any JS library.
+    Svg,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dates {
+    /// Which date of commits to show.
+    pub show: DateField,
+    /// strftime-like format string as accepted by chrono.
+    pub format: String,
+    pub timezone: Timezone,
+}
+
+impl Default for Dates {
+    fn default() -> Self {
+        Self {
+            show: DateField::default(),
+            format: DEFAULT_DATE_FORMAT.to_string(),
+            timezone: Timezone::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateField {
+    /// The date the change was originally authored.
+    #[default]
+    Author,
+    /// The date the commit was created, e.g, when it was rebased or merged.
+    Commit,
+}
+
+/// Timezone to show dates in, which is either of `local`, `utc` or an offset like `+09:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Timezone {
+    /// Timezone of the browser.
+    #[default]
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "local" => return Ok(Timezone::Local),
+            "utc" | "UTC" | "Z" => return Ok(Timezone::Utc),
+            _ => {}
+        }
+        let invalid = || format!("invalid timezone: {}", value);
+        let (sign, offset) = match value.chars().next() {
+            Some('+') => (1, &value[1..]),
+            Some('-') => (-1, &value[1..]),
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+        let number = |part: &str| match part.parse::<i32>() {
+            Ok(n) if part.chars().all(|c| c.is_ascii_digit()) => Ok(n),
+            _ => Err(invalid()),
+        };
+        let (hours, minutes) = (number(hours)?, number(minutes)?);
+        if minutes >= 60 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Timezone::Fixed)
+            .ok_or_else(invalid)
+    }
+}
+
+impl From<Timezone> for String {
+    fn from(timezone: Timezone) -> Self {
+        match timezone {
+            Timezone::Local => "local".to_string(),
+            Timezone::Utc => "utc".to_string(),
+            Timezone::Fixed(offset) => offset.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagSource {
+    /// List tags from GitHub tags API.
+    #[serde(rename = "github")]
+    GitHub,
+    /// Read `tags.json` placed under `data_url`.
+    Static,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data {
     pub title: String,
     pub file: String,
     pub query: String,
+    #[serde(default)]
+    pub thresholds: Vec<Threshold>,
+    #[serde(default)]
+    pub overlays: Vec<Overlay>,
+    #[serde(default)]
+    pub axis: Axis,
+    /// What values represent, which decides how they're formatted in place of `unit` and `format`
+    /// of the axis, so can't be set along with them.
+    pub unit: Option<Unit>,
+    /// Extra values to plot on the same chart.
+    #[serde(default)]
+    pub series: Vec<Series>,
+    /// Options of the right Y axis, which is shown when any series is placed on it.
+    #[serde(default)]
+    pub right_axis: Axis,
+    #[serde(default)]
+    pub chart_type: ChartType,
+    /// Connect datapoints across commits without value instead of breaking the line.
+    #[serde(default)]
+    pub span_gaps: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartType {
+    #[default]
+    Line,
+    /// Line which keeps the value until the next datapoint.
+    Step,
+    Bar,
+    /// Datapoints without connecting lines.
+    Scatter,
+}
+
+/// An extra line plotted along with the main value of a chart, read from the same file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Series {
+    pub title: String,
+    pub query: String,
+    #[serde(default)]
+    pub y_axis: AxisPosition,
+    /// Unit of values, which defaults to the one of the chart for the left axis. Ticks of the
+    /// right axis are formatted by the unit of the first series placed on it.
+    pub unit: Option<Unit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisPosition {
+    #[default]
+    Left,
+    Right,
+}
+
+// Unknown fields can't be collected from inside internally tagged enums, so they're rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Unit {
+    /// Durations measured in `base` unit, shown in the most fitting unit of time.
+    Time {
+        #[serde(default)]
+        base: TimeUnit,
+    },
+    /// Sizes in bytes, shown with binary prefixes.
+    Bytes,
+    /// Number of `label`s per second, e.g, throughput.
+    Rate { label: Option<String> },
+    /// Percentage, or ratio where `1.0` is `100%` if `ratio` is set.
+    Percent {
+        #[serde(default)]
+        ratio: bool,
+    },
+    /// Number of `label`s, shown with SI prefixes.
+    Count { label: Option<String> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeUnit {
+    Ns,
+    Us,
+    Ms,
+    #[default]
+    S,
+}
+
+impl TimeUnit {
+    pub fn seconds(self) -> f64 {
+        match self {
+            TimeUnit::Ns => 1e-9,
+            TimeUnit::Us => 1e-6,
+            TimeUnit::Ms => 1e-3,
+            TimeUnit::S => 1.0,
+        }
+    }
+}
+
+/// Options of the Y axis of a chart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Axis {
+    pub title: Option<String>,
+    pub scale: Scale,
+    /// Start the axis at zero rather than around the lowest value. Ignored for log scale.
+    pub begin_at_zero: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Suffix appended to formatted values, e.g, `B` or `ops/s`.
+    pub unit: Option<String>,
+    pub format: Option<ValueFormat>,
+}
+
+impl Axis {
+    /// Returns true if how values are formatted is set, which conflicts with `Unit`.
+    fn sets_format(&self) -> bool {
+        self.unit.is_some() || self.format.is_some()
+    }
+
+    /// Returns the problem of `unit` set along with a format which ignores it.
+    fn format_problem(&self) -> Option<String> {
+        let format = match self.format {
+            Some(ValueFormat::Percent) => "percent",
+            Some(ValueFormat::Duration) => "duration",
+            _ => return None,
+        };
+        self.unit.as_ref().map(|unit| {
+            format!(
+                "unit '{}' can't be set along with format '{}', which has its own suffix",
+                unit, format
+            )
+        })
+    }
+
+    /// Returns the problem of `min` and `max`, which charts can't be scaled to.
+    fn bounds_problem(&self) -> Option<String> {
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if min >= max {
+                return Some(format!("min {} must be less than max {}", min, max));
+            }
+        }
+        let non_positive = self.min.into_iter().chain(self.max).any(|v| v <= 0.0);
+        if self.scale == Scale::Logarithmic && non_positive {
+            return Some("min and max of logarithmic scale must be positive".to_owned());
+        }
+        None
+    }
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        Self {
+            title: None,
+            scale: Scale::default(),
+            begin_at_zero: true,
+            min: None,
+            max: None,
+            unit: None,
+            format: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scale {
+    #[default]
+    Linear,
+    Logarithmic,
+}
+
+/// How to format values on the axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueFormat {
+    /// With SI prefixes in powers of 1000, e.g, `1.5 k`.
+    #[default]
+    Decimal,
+    /// With IEC prefixes in powers of 1024, e.g, `1.5 Ki`.
+    Binary,
+    /// Ratio shown in percentage, where `1.0` is `100%`.
+    Percent,
+    /// Seconds shown in the most fitting unit of time, e.g, `250 ms`.
+    Duration,
+}
+
+/// A budget line which values are expected not to cross.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Threshold {
+    pub value: f64,
+    pub label: Option<String>,
+    pub color: Option<String>,
+    /// Values on this side of the threshold violates it.
+    #[serde(default)]
+    pub direction: Direction,
+}
+
+impl Threshold {
+    pub fn is_violated_by(&self, value: f64) -> bool {
+        match self.direction {
+            Direction::Above => value > self.value,
+            Direction::Below => value < self.value,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        self.label.clone().unwrap_or_else(|| self.value.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Above,
+    Below,
+}
+
+/// An extra line computed from the values of the chart.
+// Unknown fields are rejected for the same reason as `Unit`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Overlay {
+    /// Simple moving average over the `window` datapoints.
+    Sma { window: usize },
+    /// Exponential moving average with smoothing factor of `2 / (window + 1)`.
+    Ema { window: usize },
+    /// Linear regression of all datapoints.
+    Trend,
+}
+
+impl Overlay {
+    pub fn label(&self) -> String {
+        match self {
+            Overlay::Sma { window } => format!("SMA({})", window),
+            Overlay::Ema { window } => format!("EMA({})", window),
+            Overlay::Trend => "Trend".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(data: serde_json::Value) -> Config {
+        serde_json::from_value(json!({
+            "on": "#loupe",
+            "repo": "line/decaton",
+            "data_url": "https://line.github.io/decaton/commit-data",
+            "components": {"show_table": true, "show_range": false},
+            "data": data,
+        }))
+        .unwrap()
+    }
+
+    fn chart(title: &str) -> serde_json::Value {
+        json!({"title": title, "file": "benchmark.json", "query": "throughput"})
+    }
+
+    #[test]
+    fn test_is_valid_repo() {
+        for repo in &["line/decaton", "a/b", "my-org/my_repo.rs", "A1/.github"] {
+            assert!(is_valid_repo(repo), "{} is rejected", repo);
+        }
+        for repo in &[
+            "",
+            "decaton",
+            "/decaton",
+            "line/",
+            "line/decaton/x",
+            "li ne/decaton",
+        ] {
+            assert!(!is_valid_repo(repo), "{} is accepted", repo);
+        }
+    }
+
+    #[test]
+    fn test_data_url_problem() {
+        for url in &[
+            "https://line.github.io/decaton/commit-data",
+            "http://localhost:8080/",
+            "/commit-data",
+            "./commit-data",
+            "commit-data",
+        ] {
+            assert_eq!(data_url_problem(url), None, "{} is rejected", url);
+        }
+        for url in &[
+            "",
+            "ftp://example.com/data",
+            "javascript:alert(1)",
+            "https://example.com/data?v=1",
+            "https://example.com/data#top",
+            "commit data",
+            "https://exa mple.com/",
+            "http://[::1/",
+        ] {
+            assert!(data_url_problem(url).is_some(), "{} is accepted", url);
+        }
+    }
+
+    #[test]
+    fn test_valid_config() {
+        assert!(config(json!([chart("a"), chart("b")]))
+            .validate()
+            .is_empty());
+        assert!(config(json!([])).validate().is_empty());
+    }
+
+    #[test]
+    fn test_all_problems_reported() {
+        let mut config = config(json!([
+            chart("a"),
+            {"title": "a", "file": "f.json", "query": "[", "axis": {"min": 1, "max": 1}},
+        ]));
+        config.repo = "decaton".to_string();
+        config.data_url = String::new();
+        let problems = config.validate();
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+    }
+
+    #[test]
+    fn test_unknown_fields_collected() {
+        let (config, unknown) = Config::parse(json!({
+            "on": "#loupe",
+            "repo": "decaton",
+            "data_url": "https://line.github.io/decaton/commit-data",
+            "colour": "red",
+            "components": {"show_table": true, "show_range": false, "show_graph": true},
+            "data": [{"title": "a", "file": "f.json", "query": "[", "axis": {"mni": 1}}],
+        }))
+        .unwrap();
+        assert_eq!(
+            unknown,
+            vec!["colour", "components.show_graph", "data[0].axis.mni"]
+        );
+        // The rest is still validated.
+        assert_eq!(config.validate().len(), 2);
+
+        // Unknown fields inside a unit are rejected instead.
+        let mut chart = chart("a");
+        chart["unit"] = json!({"type": "time", "bsae": "ns"});
+        let result = Config::parse(json!({
+            "on": "#loupe",
+            "repo": "line/decaton",
+            "data_url": "https://line.github.io/decaton/commit-data",
+            "components": {"show_table": true, "show_range": false},
+            "data": [chart],
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_axis_bounds_problem() {
+        let axis = |value: serde_json::Value| -> Axis { serde_json::from_value(value).unwrap() };
+        assert_eq!(axis(json!({"min": 0, "max": 1})).bounds_problem(), None);
+        assert_eq!(axis(json!({"min": 1})).bounds_problem(), None);
+        assert!(axis(json!({"min": 1, "max": 1})).bounds_problem().is_some());
+        assert!(axis(json!({"min": 2, "max": 1})).bounds_problem().is_some());
+        let log = json!({"scale": "logarithmic", "max": 0});
+        assert!(axis(log).bounds_problem().is_some());
+    }
+
+    #[test]
+    fn test_axis_format_problem() {
+        let axis = |value: serde_json::Value| -> Axis { serde_json::from_value(value).unwrap() };
+        assert_eq!(
+            axis(json!({"unit": "B", "format": "binary"})).format_problem(),
+            None
+        );
+        assert_eq!(axis(json!({"format": "percent"})).format_problem(), None);
+        assert!(axis(json!({"unit": "%", "format": "percent"}))
+            .format_problem()
+            .is_some());
+        assert!(axis(json!({"unit": "s", "format": "duration"}))
+            .format_problem()
+            .is_some());
+    }
+
+    #[test]
+    fn test_unit_along_with_axis_format() {
+        let data = json!([{
+            "title": "a", "file": "f.json", "query": "v",
+            "unit": {"type": "bytes"}, "axis": {"format": "binary"},
+        }]);
+        assert_eq!(config(data).validate().len(), 1);
+        let data = json!([{
+            "title": "a", "file": "f.json", "query": "v",
+            "axis": {"unit": "B"}, "right_axis": {"unit": "%"},
+            "series": [
+                {"title": "b", "query": "w", "unit": {"type": "bytes"}},
+                {"title": "c", "query": "x", "y_axis": "right", "unit": {"type": "percent"}},
+            ],
+        }]);
+        assert_eq!(config(data).validate().len(), 2);
+    }
+
+    fn timezone(value: &str) -> Result<Timezone, String> {
+        Timezone::try_from(value.to_string())
+    }
+
+    #[test]
+    fn test_timezone() {
+        assert_eq!(timezone("local"), Ok(Timezone::Local));
+        for utc in &["utc", "UTC", "Z"] {
+            assert_eq!(timezone(utc), Ok(Timezone::Utc));
+        }
+        let east = FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(timezone("+09:00"), Ok(Timezone::Fixed(east)));
+        assert_eq!(timezone("+9"), Ok(Timezone::Fixed(east)));
+        let west = FixedOffset::west_opt(3 * 3600 + 30 * 60).unwrap();
+        assert_eq!(timezone("-03:30"), Ok(Timezone::Fixed(west)));
+    }
+
+    #[test]
+    fn test_invalid_timezone() {
+        for value in &[
+            "", "Local", "09:00", "+", "+09:", "+:30", "+-09", "++09", "+09:-30", "+09:60", "+24",
+            "+abc",
+        ] {
+            assert!(timezone(value).is_err(), "{} is accepted", value);
+        }
+    }
+
+    #[test]
+    fn test_timezone_round_trip() {
+        for value in &["local", "utc", "+09:00", "-03:30"] {
+            let timezone = timezone(value).unwrap();
+            assert_eq!(String::from(timezone), *value);
+        }
+    }
 }
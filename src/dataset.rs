@@ -1,42 +1,200 @@
-use crate::api::{self, Api, CommitListRequest, CommitMetadataRequest};
-use crate::commit::CommitInfo;
+use crate::api::{
+    self, Api, CommitCompareRequest, CommitListRequest, CommitMetadataRequest, ComparePage,
+    Manifest, ManifestRequest, Tag, TagListRequest, COMMITS_PAGE_SIZE, TAGS_PAGE_SIZE,
+};
+use crate::commit::{self, CommitInfo};
 use crate::range::Range;
 use http::StatusCode;
+use jmespatch::Rcvar;
 use log::*;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
 use std::hash::Hash;
 use std::rc::Rc;
 use yew::services::fetch::FetchTask;
 
-const COMMITS_PAGE_SIZE: u32 = 50;
+/// Upper limit of pages to list one after another, for a date-windowed range or to find commits
+/// having metadata, to not exhaust API rate limit.
+const MAX_LISTED_PAGES: u32 = 20;
+/// Max number of pages to list tags, not to list all tags of repositories having too many.
+const MAX_TAG_PAGES: u32 = 10;
+/// Number of times to deliver partial datasets while fetching metadata.
+const PROGRESS_STEPS: usize = 10;
 
+/// Set of APIs to collect commits and their metadata from.
 #[derive(Debug)]
+pub struct Apis<A, P, M, N, T> {
+    pub commits: Rc<RefCell<A>>,
+    pub compare: Rc<RefCell<P>>,
+    pub metadata: Rc<RefCell<M>>,
+    pub manifest: Rc<RefCell<N>>,
+    pub tags: Rc<RefCell<T>>,
+}
+
+impl<A, P, M, N, T> Clone for Apis<A, P, M, N, T> {
+    fn clone(&self) -> Self {
+        Self {
+            commits: Rc::clone(&self.commits),
+            compare: Rc::clone(&self.compare),
+            metadata: Rc::clone(&self.metadata),
+            manifest: Rc::clone(&self.manifest),
+            tags: Rc::clone(&self.tags),
+        }
+    }
+}
+
+/// Stages of making view data, to tell where it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    CommitListing,
+    MetadataFetch,
+    Query,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Stage::CommitListing => "commit listing",
+            Stage::MetadataFetch => "metadata fetch",
+            Stage::Query => "query",
+        })
+    }
+}
+
+/// Status of the datapoint of a commit, which tells why its value is missing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Ok,
+    /// Metadata file doesn't exist for the commit.
+    NotFound,
+    FetchError(String),
+    ParseError(String),
+    /// The query returned something other than a number, or failed on the metadata.
+    NonNumeric(String),
+}
+
+impl Status {
+    /// Returns the short label to show in place of the missing value.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Ok => "N/A",
+            Status::NotFound => "not found",
+            Status::FetchError(_) => "fetch error",
+            Status::ParseError(_) => "parse error",
+            Status::NonNumeric(_) => "non-numeric",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Ok => f.write_str("ok"),
+            Status::NotFound => f.write_str("metadata not found"),
+            Status::FetchError(e) => write!(f, "failed to fetch metadata: {}", e),
+            Status::ParseError(e) => write!(f, "malformed metadata: {}", e),
+            Status::NonNumeric(e) => write!(f, "no value: {}", e),
+        }
+    }
+}
+
+/// Commits in range and their metadata. While metadata are being fetched, partial datasets are
+/// delivered as responses arrive, which have `loaded` less than `total`.
+#[derive(Debug, Clone, Default)]
 pub struct CommitDataSet {
     pub commits: Vec<CommitInfo>,
-    pub metadata: HashMap<String, String>,
+    /// Parsed metadata of each commit.
+    pub metadata: HashMap<String, Rcvar>,
+    /// Status of commits whose metadata couldn't be obtained.
+    pub failures: HashMap<String, Status>,
+    /// Names of tags on each commit, including those on the older commits not in `commits`.
+    pub tags: HashMap<String, Vec<String>>,
+    /// Number of commits whose metadata request completed, either successfully or not.
+    pub loaded: usize,
+    /// Number of commits to request metadata for.
+    pub total: usize,
+    /// True if listing stopped before the end of the range, so its older commits are missing.
+    pub truncated: bool,
+    /// Shas of all commits in range from the newest, to carry tags on commits not sampled.
+    listed: Rc<Vec<String>>,
+}
+
+/// Commits listed for a range, shared among datasets of all files.
+#[derive(Debug, Clone)]
+pub struct CommitList {
+    /// Commits in range, which are not sampled yet.
+    pub commits: Vec<CommitInfo>,
+    /// Pages listed for a count-bounded range, which is `None` for ranges bounded otherwise.
+    pages: Option<Pages>,
+    /// True if listing stopped at `MAX_LISTED_PAGES` before the end of the range.
+    truncated: bool,
+}
+
+/// Commits listed page by page for a count-bounded range before they're filtered, from which the
+/// range is extended with older commits when it has too few commits having metadata.
+#[derive(Debug, Clone)]
+struct Pages {
+    repo: String,
+    commits: Vec<CommitInfo>,
+    /// Number of pages listed so far.
+    listed: u32,
+    /// True if the last page reached the end of the history.
+    exhausted: bool,
 }
 
 impl CommitDataSet {
-    pub fn collect_range<A, M, C>(
-        commits_api: Rc<RefCell<A>>,
-        meta_api: Rc<RefCell<M>>,
+    pub fn is_complete(&self) -> bool {
+        self.loaded == self.total
+    }
+
+    /// Returns the dataset with the tags attached, where tags on commits which aren't sampled are
+    /// carried to the nearest sampled one.
+    pub fn with_tags(&self, tags: &HashMap<String, Vec<String>>) -> Self {
+        Self {
+            tags: commit::carry_tags(&self.listed, &self.commits, tags.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// List commits in the range. The listed commits are then passed to `collect_metadata` for
+    /// each file.
+    pub fn list_range<A, P, M, N, T, C>(
+        apis: Apis<A, P, M, N, T>,
         repo: &str,
-        file: &str,
         range: Range,
         callback: C,
     ) where
-        A: Api<CommitListRequest, Vec<CommitInfo>>,
-        M: Api<CommitMetadataRequest, String> + 'static,
-        C: FnOnce(Result<Self, api::Error>) + 'static,
+        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
+        P: Api<CommitCompareRequest, ComparePage> + 'static,
+        M: 'static,
+        N: 'static,
+        T: 'static,
+        C: FnOnce(Result<CommitList, api::Error>) + 'static,
     {
+        if let Some(base) = range.base.as_ref() {
+            let req = CommitCompareRequest {
+                repo: repo.to_owned(),
+                base: base.to_owned(),
+                head: range.head().to_owned(),
+                page: 1,
+            };
+            Self::collect_compare_page(apis, req, range, Vec::new(), callback);
+            return;
+        }
+        if range.is_windowed() {
+            Self::collect_window_page(apis, repo.to_owned(), range, 1, Vec::new(), callback);
+            return;
+        }
+
         let pages = range.pages_for_batch(COMMITS_PAGE_SIZE);
         let from = range.from.clone();
-        let file = file.to_owned();
+        let until = range.until;
+        let repo_cp = repo.to_owned();
+        let commits_api = Rc::clone(&apis.commits);
 
         let commits_sg = Rc::new(SyncGroup::new((1..=pages).collect(), move |resp| {
-            Self::handle_completed_commits_fetch(file, range, meta_api, callback, resp);
+            Self::handle_completed_commits_fetch(repo_cp, range, callback, resp);
         }));
 
         for i in 1..=pages {
@@ -45,8 +203,9 @@ impl CommitDataSet {
                 &CommitListRequest {
                     repo: repo.to_owned(),
                     from: from.as_ref().map(|s| s.to_owned()),
+                    since: None,
+                    until,
                     page: i,
-                    count: COMMITS_PAGE_SIZE,
                 },
                 move |resp| {
                     sg.recv(i, resp);
@@ -58,30 +217,143 @@ impl CommitDataSet {
                         commits_sg.in_flight(i, task);
                     }
                 }
-                Err(e) => error!("Failed to call API for commits listing: {:?}", e),
+                Err(e) => {
+                    error!("Failed to call API for commits listing: {:?}", e);
+                    commits_sg.update_state(
+                        i,
+                        CollectState::Present(Err(api::Error::Fetch(e.to_string()))),
+                    );
+                }
             }
         }
         commits_sg.try_complete();
     }
 
-    fn handle_completed_commits_fetch<M, C>(
-        file: String,
+    /// List tags in the repository, which are attached to datasets by `with_tags`.
+    pub fn list_tags<A, P, M, N, T, C>(apis: &Apis<A, P, M, N, T>, repo: &str, callback: C)
+    where
+        T: Api<TagListRequest, Vec<Tag>> + 'static,
+        C: FnOnce(HashMap<String, Vec<String>>) + 'static,
+    {
+        let req = TagListRequest {
+            repo: repo.to_owned(),
+            page: 1,
+        };
+        list_tag_pages(Rc::clone(&apis.tags), req, Vec::new(), move |tags| {
+            callback(Tag::by_commit(tags))
+        });
+    }
+
+    /// List commits between two refs through the compare API page by page, until all of them are
+    /// listed.
+    fn collect_compare_page<A, P, M, N, T, C>(
+        apis: Apis<A, P, M, N, T>,
+        req: CommitCompareRequest,
+        range: Range,
+        mut commits: Vec<CommitInfo>,
+        callback: C,
+    ) where
+        A: 'static,
+        P: Api<CommitCompareRequest, ComparePage> + 'static,
+        M: 'static,
+        N: 'static,
+        T: 'static,
+        C: FnOnce(Result<CommitList, api::Error>) + 'static,
+    {
+        let compare_api = Rc::clone(&apis.compare);
+        call_single(&compare_api, &req.clone(), move |resp| {
+            let page = match resp {
+                Ok(page) => page,
+                Err(e) => {
+                    callback(Err(e));
+                    return;
+                }
+            };
+            let exhausted = page.commits.is_empty();
+            commits.extend(page.commits);
+            if !exhausted && (commits.len() as u32) < page.total_commits {
+                let req = CommitCompareRequest {
+                    page: req.page + 1,
+                    ..req
+                };
+                Self::collect_compare_page(apis, req, range, commits, callback);
+                return;
+            }
+            // Compare API lists commits in chronological order, whereas commits API lists
+            // the newest first.
+            commits.reverse();
+            Self::handle_listed_commits(range, callback, commits, None, false);
+        });
+    }
+
+    /// List commits within the date window of the range page by page, until the API returns
+    /// a page shorter than the page size.
+    fn collect_window_page<A, P, M, N, T, C>(
+        apis: Apis<A, P, M, N, T>,
+        repo: String,
+        range: Range,
+        page: u32,
+        mut commits: Vec<CommitInfo>,
+        callback: C,
+    ) where
+        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
+        P: 'static,
+        M: 'static,
+        N: 'static,
+        T: 'static,
+        C: FnOnce(Result<CommitList, api::Error>) + 'static,
+    {
+        let req = CommitListRequest {
+            repo: repo.clone(),
+            from: range.from.clone(),
+            since: range.since,
+            until: range.until,
+            page,
+        };
+        let commits_api = Rc::clone(&apis.commits);
+        call_single(&commits_api, &req, move |resp| {
+            let batch = match resp {
+                Ok(batch) => batch,
+                Err(e) => {
+                    callback(Err(e));
+                    return;
+                }
+            };
+            let exhausted = (batch.len() as u32) < COMMITS_PAGE_SIZE;
+            commits.extend(batch);
+            if !exhausted && page < MAX_LISTED_PAGES {
+                Self::collect_window_page(apis, repo, range, page + 1, commits, callback);
+                return;
+            }
+            if !exhausted {
+                warn!(
+                    "Stopped listing commits in window at {} commits, older ones are ignored",
+                    commits.len()
+                );
+            }
+            Self::handle_listed_commits(range, callback, commits, None, !exhausted);
+        });
+    }
+
+    fn handle_completed_commits_fetch<C>(
+        repo: String,
         range: Range,
-        meta_api: Rc<RefCell<M>>,
         callback: C,
         resp: HashMap<u32, Result<Vec<CommitInfo>, api::Error>>,
     ) where
-        M: Api<CommitMetadataRequest, String> + 'static,
-        C: FnOnce(Result<Self, api::Error>) + 'static,
+        C: FnOnce(Result<CommitList, api::Error>) + 'static,
     {
         let mut commits = Vec::new();
+        let mut exhausted = false;
 
         // First flatten all commits into linear vector
         let mut kvs: Vec<_> = resp.into_iter().collect();
         kvs.sort_by_key(|(i, _)| *i);
+        let listed = kvs.len() as u32;
         for (_, batch) in kvs {
             match batch {
                 Ok(batch) => {
+                    exhausted = (batch.len() as u32) < COMMITS_PAGE_SIZE;
                     commits.extend(batch);
                 }
                 Err(e) => {
@@ -93,11 +365,316 @@ impl CommitDataSet {
             }
         }
 
-        // Select sampled element from it
-        let commits = range.sample(commits);
+        let pages = Pages {
+            repo,
+            commits: commits.clone(),
+            listed,
+            exhausted,
+        };
+        Self::handle_listed_commits(range, callback, commits, Some(pages), false);
+    }
 
-        // For each commit issue metadata fetch
-        Self::request_commits_metadata(file, meta_api, callback, commits);
+    /// Filter commits in range. `pages` are given for a count-bounded range to extend it when
+    /// `skip_missing` is set.
+    fn handle_listed_commits<C>(
+        range: Range,
+        callback: C,
+        mut commits: Vec<CommitInfo>,
+        pages: Option<Pages>,
+        truncated: bool,
+    ) where
+        C: FnOnce(Result<CommitList, api::Error>) + 'static,
+    {
+        range.filter(&mut commits);
+        callback(Ok(CommitList {
+            commits,
+            pages,
+            truncated,
+        }));
+    }
+
+    /// Select commits to show from the listed commits, and request metadata of the file for them.
+    /// The callback is called with partial datasets until the complete one is delivered, or with
+    /// an error instead of it if no metadata could be fetched due to errors.
+    pub fn collect_metadata<A, P, M, N, T, C>(
+        apis: &Apis<A, P, M, N, T>,
+        file: String,
+        range: Range,
+        list: &CommitList,
+        mut callback: C,
+    ) where
+        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
+        P: 'static,
+        M: Api<CommitMetadataRequest, Rcvar> + 'static,
+        N: Api<ManifestRequest, Manifest> + 'static,
+        T: 'static,
+        C: FnMut(Result<Self, api::Error>) + 'static,
+    {
+        let apis = apis.clone();
+        let commits = list.commits.clone();
+        let pages = list.pages.clone();
+        // Listed commits are kept to carry tags, and replaced when the range is extended with
+        // older ones.
+        let listed = Rc::new(RefCell::new(shas(&commits)));
+        let listed_cp = Rc::clone(&listed);
+        let truncated = list.truncated;
+        let callback = move |resp: Result<Self, api::Error>| {
+            callback(resp.map(|mut ds| {
+                ds.listed = Rc::new(listed_cp.borrow().clone());
+                ds.truncated = truncated;
+                ds
+            }))
+        };
+
+        if !range.skip_missing {
+            // Select sampled element from it
+            let commits = range.sample(commits);
+            // For each commit issue metadata fetch
+            Self::request_commits_metadata(file, apis.metadata, callback, commits);
+            return;
+        }
+
+        let manifest_api = Rc::clone(&apis.manifest);
+        call_single(&manifest_api, &ManifestRequest, move |resp| match resp {
+            Ok(manifest) => Self::request_in_manifest(
+                apis, file, range, commits, pages, manifest, listed, callback,
+            ),
+            Err(e) => {
+                debug!(
+                    "Manifest unavailable, probing metadata of all commits: {}",
+                    e
+                );
+                let callback = Rc::new(RefCell::new(callback));
+                let probed = Self::default();
+                Self::probe_available(apis, file, range, commits, pages, probed, listed, callback);
+            }
+        });
+    }
+
+    /// Request metadata of commits which the manifest lists for the file. A count-bounded range
+    /// is extended with older commits until it has enough of them to sample.
+    #[allow(clippy::too_many_arguments)]
+    fn request_in_manifest<A, P, M, N, T, C>(
+        apis: Apis<A, P, M, N, T>,
+        file: String,
+        range: Range,
+        commits: Vec<CommitInfo>,
+        pages: Option<Pages>,
+        manifest: Manifest,
+        listed: Rc<RefCell<Vec<String>>>,
+        callback: C,
+    ) where
+        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
+        P: 'static,
+        M: Api<CommitMetadataRequest, Rcvar> + 'static,
+        N: 'static,
+        T: 'static,
+        C: FnMut(Result<Self, api::Error>) + 'static,
+    {
+        let available: Vec<_> = commits
+            .iter()
+            .filter(|c| manifest.contains(&file, &c.sha))
+            .cloned()
+            .collect();
+        let pages = match pages {
+            Some(pages) if available.len() < range.available_target() => pages,
+            _ => {
+                let commits = range.sample_available(available);
+                Self::request_commits_metadata(file, apis.metadata, callback, commits);
+                return;
+            }
+        };
+        let (apis_cp, range_cp) = (apis.clone(), range.clone());
+        Self::extend_range(
+            &apis_cp,
+            &range_cp,
+            pages,
+            commits.len(),
+            move |extended| match extended {
+                Some((commits, pages)) => {
+                    listed.replace(shas(&commits));
+                    Self::request_in_manifest(
+                        apis,
+                        file,
+                        range,
+                        commits,
+                        Some(pages),
+                        manifest,
+                        listed,
+                        callback,
+                    );
+                }
+                None => {
+                    let commits = range.sample_available(available);
+                    Self::request_commits_metadata(file, apis.metadata, callback, commits);
+                }
+            },
+        );
+    }
+
+    /// Probe metadata of commits to sample among those having it. A count-bounded range is
+    /// extended with older commits, which are probed in turn, until enough of them are found.
+    /// Commits probed in earlier rounds are given as `probed`.
+    #[allow(clippy::too_many_arguments)]
+    fn probe_available<A, P, M, N, T, C>(
+        apis: Apis<A, P, M, N, T>,
+        file: String,
+        range: Range,
+        commits: Vec<CommitInfo>,
+        pages: Option<Pages>,
+        probed: Self,
+        listed: Rc<RefCell<Vec<String>>>,
+        callback: Rc<RefCell<C>>,
+    ) where
+        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
+        P: 'static,
+        M: Api<CommitMetadataRequest, Rcvar> + 'static,
+        N: 'static,
+        T: 'static,
+        C: FnMut(Result<Self, api::Error>) + 'static,
+    {
+        let meta_api = Rc::clone(&apis.metadata);
+        // The range is extended only once probing of this round completes, whereas partial
+        // datasets are shown as they arrive.
+        let mut next = Some((apis, file.clone(), pages, listed));
+        let on_probed = move |resp: Result<Self, api::Error>| {
+            let failed = resp.is_err();
+            let ds = match resp {
+                Ok(ds) => probed.merged(ds),
+                Err(e) if probed.metadata.is_empty() => {
+                    (callback.borrow_mut())(Err(e));
+                    return;
+                }
+                // Show what earlier rounds found rather than failing for this round.
+                Err(_) => probed.clone(),
+            };
+            let extend =
+                !failed && ds.is_complete() && ds.metadata.len() < range.available_target();
+            let (apis, file, pages, listed) = match next.take() {
+                Some((apis, file, Some(pages), listed)) if extend => (apis, file, pages, listed),
+                taken => {
+                    next = taken;
+                    (callback.borrow_mut())(Ok(ds.sample_available(&range)));
+                    return;
+                }
+            };
+            let (apis_cp, range_cp) = (apis.clone(), range.clone());
+            let callback = Rc::clone(&callback);
+            let shown = ds.commits.len();
+            Self::extend_range(&apis_cp, &range, pages, shown, move |extended| {
+                let range = range_cp;
+                let (commits, pages) = match extended {
+                    Some(extended) => extended,
+                    None => {
+                        (callback.borrow_mut())(Ok(ds.sample_available(&range)));
+                        return;
+                    }
+                };
+                listed.replace(shas(&commits));
+                let fresh = {
+                    let probed: HashSet<_> = ds.commits.iter().map(|c| &c.sha).collect();
+                    commits
+                        .into_iter()
+                        .filter(|c| !probed.contains(&c.sha))
+                        .collect()
+                };
+                let pages = Some(pages);
+                Self::probe_available(apis, file, range, fresh, pages, ds, listed, callback);
+            });
+        };
+        Self::request_commits_metadata(file, meta_api, on_probed, commits);
+    }
+
+    /// Extend the count-bounded range past its count with commits already listed in pages, or
+    /// by listing the next page if they're used up. The callback is given commits in the extended
+    /// range, or `None` if no more commits can be listed.
+    fn extend_range<A, P, M, N, T, C>(
+        apis: &Apis<A, P, M, N, T>,
+        range: &Range,
+        mut pages: Pages,
+        shown: usize,
+        callback: C,
+    ) where
+        A: Api<CommitListRequest, Vec<CommitInfo>> + 'static,
+        C: FnOnce(Option<(Vec<CommitInfo>, Pages)>) + 'static,
+    {
+        let mut commits = pages.commits.clone();
+        range.filter_unbounded(&mut commits);
+        if commits.len() > shown {
+            callback(Some((commits, pages)));
+            return;
+        }
+        if pages.exhausted || pages.listed >= MAX_LISTED_PAGES {
+            callback(None);
+            return;
+        }
+        let req = CommitListRequest {
+            repo: pages.repo.clone(),
+            from: range.from.clone(),
+            since: None,
+            until: range.until,
+            page: pages.listed + 1,
+        };
+        let range = range.clone();
+        call_single(&apis.commits, &req, move |resp| match resp {
+            Ok(batch) => {
+                pages.listed += 1;
+                pages.exhausted = (batch.len() as u32) < COMMITS_PAGE_SIZE;
+                pages.commits.extend(batch);
+                let mut commits = pages.commits.clone();
+                range.filter_unbounded(&mut commits);
+                callback(Some((commits, pages)));
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to list older commits to find those having metadata: {}",
+                    e
+                );
+                callback(None);
+            }
+        });
+    }
+
+    /// Merge the dataset of older commits into this one.
+    fn merged(&self, older: Self) -> Self {
+        let mut merged = self.clone();
+        merged.commits.extend(older.commits);
+        merged.metadata.extend(older.metadata);
+        merged.failures.extend(older.failures);
+        merged.loaded += older.loaded;
+        merged.total += older.total;
+        merged
+    }
+
+    /// Sample commits among those having metadata.
+    fn sample_available(self, range: &Range) -> Self {
+        let Self {
+            commits,
+            mut metadata,
+            mut failures,
+            tags,
+            loaded,
+            total,
+            truncated,
+            listed,
+        } = self;
+        let available = commits
+            .into_iter()
+            .filter(|c| metadata.contains_key(&c.sha))
+            .collect();
+        let commits = range.sample_available(available);
+        metadata.retain(|sha, _| commits.iter().any(|c| &c.sha == sha));
+        failures.retain(|sha, _| commits.iter().any(|c| &c.sha == sha));
+        Self {
+            commits,
+            metadata,
+            failures,
+            tags,
+            loaded,
+            total,
+            truncated,
+            listed,
+        }
     }
 
     fn request_commits_metadata<M, C>(
@@ -106,75 +683,239 @@ impl CommitDataSet {
         callback: C,
         commits: Vec<CommitInfo>,
     ) where
-        M: Api<CommitMetadataRequest, String> + 'static,
-        C: FnOnce(Result<Self, api::Error>) + 'static,
+        M: Api<CommitMetadataRequest, Rcvar> + 'static,
+        C: FnMut(Result<Self, api::Error>) + 'static,
     {
         let commit_ids: Vec<_> = commits.iter().map(|c| c.sha.clone()).collect();
+        let collector = Rc::new(MetadataCollector::new(commits, callback));
+        // Show commits listed so far before any metadata arrives.
+        collector.deliver();
 
-        let meta_sg = Rc::new(SyncGroup::new(
-            commits.iter().map(|c| c.sha.clone()).collect(),
-            move |resp| {
-                Self::handle_completed_metadata_fetch(callback, commits, resp);
-            },
-        ));
-        for c in commit_ids {
-            let sg = Rc::clone(&meta_sg);
-            let sha = c.clone();
+        for sha in commit_ids {
+            let collector_cp = Rc::clone(&collector);
+            let sha_cp = sha.clone();
             let ret = meta_api.borrow_mut().call(
                 &CommitMetadataRequest {
-                    commit: c.clone(),
+                    commit: sha.clone(),
                     file: file.to_owned(),
                 },
-                move |resp| {
-                    sg.recv(sha, resp);
-                },
+                move |resp| collector_cp.recv(sha_cp, resp),
             );
             match ret {
                 Ok(task) => {
                     if let Some(task) = task {
-                        meta_sg.in_flight(c, task);
+                        collector.in_flight(sha, task);
                     }
                 }
-                Err(e) => error!("Failed to call API for commit metadata: {:?}", e),
+                Err(e) => {
+                    error!("Failed to call API for commit metadata: {:?}", e);
+                    collector.recv(sha, Err(api::Error::Fetch(e.to_string())));
+                }
             }
         }
-        meta_sg.try_complete();
+        collector.issued();
     }
+}
 
-    fn handle_completed_metadata_fetch<C>(
-        callback: C,
-        commits: Vec<CommitInfo>,
-        resp: HashMap<String, Result<String, api::Error>>,
-    ) where
-        C: FnOnce(Result<Self, api::Error>) + 'static,
-    {
-        let mut metadata = HashMap::new();
-        for (sha, data) in resp.into_iter() {
-            match data {
-                Ok(data) => {
-                    metadata.insert(sha, data);
-                }
-                Err(e) => {
-                    use api::Error::*;
-                    match e {
-                        Fetch(e) => error!("Failed to get commit metadata for {}: {:?}", sha, e),
-                        Http(status) => {
-                            if status == StatusCode::NOT_FOUND {
-                                debug!("Metadata file 404 not found: {}", sha);
-                            } else {
-                                error!("Failed to get commit metadata for {}: {:?}", sha, status);
-                            }
-                        }
-                    }
-                }
+/// Collects metadata of commits, delivering the dataset to the callback at every
+/// `1 / PROGRESS_STEPS` of responses so that charts are filled progressively without being
+/// rebuilt for each response.
+struct MetadataCollector<C> {
+    commits: Vec<CommitInfo>,
+    metadata: RefCell<HashMap<String, Rcvar>>,
+    failures: RefCell<HashMap<String, Status>>,
+    loaded: Cell<usize>,
+    /// Commits whose response arrived, either successfully or not.
+    received: RefCell<HashSet<String>>,
+    /// The last error other than missing metadata.
+    last_error: RefCell<Option<api::Error>>,
+    /// True while requests are being issued. Responses arriving meanwhile, which the API gave
+    /// synchronously, are delivered afterwards since the callback might call the API again.
+    issuing: Cell<bool>,
+    // Tasks are held only to keep requests alive.
+    tasks: RefCell<HashMap<String, FetchTask>>,
+    callback: RefCell<C>,
+}
+
+impl<C> MetadataCollector<C>
+where
+    C: FnMut(Result<CommitDataSet, api::Error>),
+{
+    fn new(commits: Vec<CommitInfo>, callback: C) -> Self {
+        Self {
+            commits,
+            metadata: RefCell::new(HashMap::new()),
+            failures: RefCell::new(HashMap::new()),
+            loaded: Cell::new(0),
+            received: RefCell::new(HashSet::new()),
+            last_error: RefCell::new(None),
+            issuing: Cell::new(true),
+            tasks: RefCell::new(HashMap::new()),
+            callback: RefCell::new(callback),
+        }
+    }
+
+    /// Deliver the dataset as of now, or the last error instead of the complete one if no metadata
+    /// could be fetched due to errors.
+    fn deliver(&self) {
+        let complete = self.loaded.get() == self.commits.len();
+        if complete && self.metadata.borrow().is_empty() {
+            let error = self.last_error.borrow_mut().take();
+            if let Some(e) = error {
+                (self.callback.borrow_mut())(Err(e));
+                return;
             }
         }
-        callback(Ok(Self { commits, metadata }));
+        let dataset = CommitDataSet {
+            commits: self.commits.clone(),
+            metadata: self.metadata.borrow().clone(),
+            failures: self.failures.borrow().clone(),
+            tags: HashMap::new(),
+            loaded: self.loaded.get(),
+            total: self.commits.len(),
+            truncated: false,
+            listed: Rc::default(),
+        };
+        (self.callback.borrow_mut())(Ok(dataset));
+    }
+
+    /// Stop deferring deliveries, delivering responses which arrived while issuing requests.
+    fn issued(&self) {
+        self.issuing.set(false);
+        if self.loaded.get() > 0 {
+            self.deliver();
+        }
+    }
+
+    fn in_flight(&self, sha: String, task: FetchTask) {
+        // The response might have arrived already if the API responded synchronously.
+        if self.received.borrow().contains(&sha) {
+            return;
+        }
+        self.tasks.borrow_mut().insert(sha, task);
     }
+
+    fn recv(&self, sha: String, resp: Result<Rcvar, api::Error>) {
+        self.tasks.borrow_mut().remove(&sha);
+        self.received.borrow_mut().insert(sha.clone());
+        let status = match resp {
+            Ok(var) => {
+                self.metadata.borrow_mut().insert(sha.clone(), var);
+                None
+            }
+            Err(api::Error::InvalidResponse(e)) => {
+                error!("Failed to parse commit metadata for {}: {}", sha, e);
+                Some(Status::ParseError(e))
+            }
+            Err(api::Error::Http(StatusCode::NOT_FOUND)) => {
+                debug!("Metadata file 404 not found: {}", sha);
+                Some(Status::NotFound)
+            }
+            Err(e) => {
+                error!("Failed to get commit metadata for {}: {}", sha, e);
+                let status = Status::FetchError(e.to_string());
+                self.last_error.replace(Some(e));
+                Some(status)
+            }
+        };
+        if let Some(status) = status {
+            self.failures.borrow_mut().insert(sha, status);
+        }
+
+        let loaded = self.loaded.get() + 1;
+        self.loaded.set(loaded);
+        if self.issuing.get() {
+            return;
+        }
+        let total = self.commits.len();
+        let step = (total / PROGRESS_STEPS).max(1);
+        if loaded == total || loaded.is_multiple_of(step) {
+            self.deliver();
+        }
+    }
+}
+
+fn shas(commits: &[CommitInfo]) -> Vec<String> {
+    commits.iter().map(|c| c.sha.clone()).collect()
+}
+
+/// List tags page by page, until the API returns a page shorter than the page size. Tags listed
+/// until an error are used, as tags are only annotations.
+fn list_tag_pages<T, F>(api: Rc<RefCell<T>>, req: TagListRequest, mut tags: Vec<Tag>, callback: F)
+where
+    T: Api<TagListRequest, Vec<Tag>> + 'static,
+    F: FnOnce(Vec<Tag>) + 'static,
+{
+    let api_cp = Rc::clone(&api);
+    call_single(&api, &req.clone(), move |resp| {
+        let batch = match resp {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!("Failed to list tags, proceeding without the rest: {}", e);
+                callback(tags);
+                return;
+            }
+        };
+        let exhausted = (batch.len() as u32) < TAGS_PAGE_SIZE;
+        tags.extend(batch);
+        if exhausted {
+            callback(tags);
+        } else if req.page >= MAX_TAG_PAGES {
+            warn!(
+                "Stopped listing tags at {} tags, others are ignored",
+                tags.len()
+            );
+            callback(tags);
+        } else {
+            let req = TagListRequest {
+                page: req.page + 1,
+                ..req
+            };
+            list_tag_pages(api_cp, req, tags, callback);
+        }
+    });
+}
+
+/// Call the API for a single request, keeping its fetch task alive until the response arrives.
+fn call_single<K, R, T, F>(api: &Rc<RefCell<T>>, req: &K, callback: F)
+where
+    K: Debug,
+    R: 'static,
+    T: Api<K, R>,
+    F: FnOnce(Result<R, api::Error>) + 'static,
+{
+    let sg = Rc::new(SyncGroup::new(
+        vec![()],
+        move |mut resp: HashMap<(), Result<R, api::Error>>| {
+            callback(resp.remove(&()).expect("BUG: no response in sync group"));
+        },
+    ));
+
+    let sg_cp = Rc::clone(&sg);
+    let ret = api.borrow_mut().call(req, move |resp| {
+        sg_cp.recv((), resp);
+    });
+    match ret {
+        Ok(task) => {
+            if let Some(task) = task {
+                sg.in_flight((), task);
+            }
+        }
+        Err(e) => {
+            error!("Failed to call API for {:?}: {:?}", req, e);
+            sg.update_state(
+                (),
+                CollectState::Present(Err(api::Error::Fetch(e.to_string()))),
+            );
+        }
+    }
+    sg.try_complete();
 }
 
 enum CollectState<T> {
     Vacant,
+    // The task is held only to keep the request alive.
+    #[allow(dead_code)]
     FetchInFlight(FetchTask),
     Present(T),
 }
@@ -206,13 +947,10 @@ where
     }
 
     fn is_all_ready(&self) -> bool {
-        self.states.borrow().values().all(|state| {
-            if let CollectState::Present(_) = state {
-                true
-            } else {
-                false
-            }
-        })
+        self.states
+            .borrow()
+            .values()
+            .all(|state| matches!(state, CollectState::Present(_)))
     }
 
     fn update_state(&self, key: K, state: CollectState<V>) {
@@ -251,3 +989,453 @@ where
         self.try_complete();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::UserInfo;
+    use std::time::SystemTime;
+
+    /// API responding within the call, by the given function of the request.
+    struct SyncApi<F>(F);
+
+    impl<K, R, F> Api<K, R> for SyncApi<F>
+    where
+        K: Debug,
+        F: FnMut(&K) -> Result<R, api::Error>,
+    {
+        fn call<C>(&mut self, req: &K, callback: C) -> Result<Option<FetchTask>, anyhow::Error>
+        where
+            C: FnOnce(Result<R, api::Error>) + 'static,
+        {
+            callback((self.0)(req));
+            Ok(None)
+        }
+    }
+
+    type Respond = Box<dyn FnOnce(Result<Rcvar, api::Error>)>;
+
+    /// Metadata API holding requests until `respond_all` is called.
+    #[derive(Default)]
+    struct DeferredApi {
+        pending: Vec<Respond>,
+    }
+
+    impl Api<CommitMetadataRequest, Rcvar> for DeferredApi {
+        fn call<C>(
+            &mut self,
+            _: &CommitMetadataRequest,
+            callback: C,
+        ) -> Result<Option<FetchTask>, anyhow::Error>
+        where
+            C: FnOnce(Result<Rcvar, api::Error>) + 'static,
+        {
+            self.pending.push(Box::new(callback));
+            Ok(None)
+        }
+    }
+
+    fn respond_all(api: &Rc<RefCell<DeferredApi>>) {
+        let pending = std::mem::take(&mut api.borrow_mut().pending);
+        for respond in pending {
+            respond(Ok(metadata()));
+        }
+    }
+
+    fn metadata() -> Rcvar {
+        Rcvar::new(jmespatch::Variable::Null)
+    }
+
+    fn commit(sha: &str) -> CommitInfo {
+        let user = UserInfo {
+            name: "user".to_string(),
+            email: "user@example.com".to_string(),
+        };
+        CommitInfo {
+            sha: sha.to_string(),
+            author: user.clone(),
+            author_date: SystemTime::UNIX_EPOCH,
+            committer: user,
+            commit_date: SystemTime::UNIX_EPOCH,
+            message: String::new(),
+            view_url: format!("https://github.com/owner/repo/commit/{}", sha),
+            parents: Vec::new(),
+        }
+    }
+
+    const PAGE: usize = COMMITS_PAGE_SIZE as usize;
+
+    /// Sha of the commit at the index from the newest.
+    fn sha(index: usize) -> String {
+        format!("c{:03}", index)
+    }
+
+    fn shas_from(start: usize, len: usize) -> Vec<String> {
+        (start..start + len).map(sha).collect()
+    }
+
+    /// Commits from the newest, whose shas tell their index.
+    fn history(len: usize) -> Vec<CommitInfo> {
+        (0..len).map(|i| commit(&sha(i))).collect()
+    }
+
+    type Delivered = Rc<RefCell<Vec<CommitDataSet>>>;
+
+    /// Returns the callback recording datasets delivered to it.
+    fn recorder() -> (Delivered, impl FnMut(Result<CommitDataSet, api::Error>)) {
+        let delivered = Rc::new(RefCell::new(Vec::new()));
+        let delivered_cp = Rc::clone(&delivered);
+        let callback = move |resp: Result<CommitDataSet, api::Error>| {
+            delivered_cp
+                .borrow_mut()
+                .push(resp.expect("dataset should be delivered"));
+        };
+        (delivered, callback)
+    }
+
+    /// Collect the range from the history, where only commits from `with_metadata` on have
+    /// metadata. The manifest is unavailable unless `manifest` is set. Returns the datasets
+    /// delivered with tags attached, the pages of commits listed and the commits whose metadata were requested.
+    fn collect(
+        history: Vec<CommitInfo>,
+        with_metadata: usize,
+        manifest: bool,
+        range: Range,
+    ) -> (Vec<CommitDataSet>, Vec<u32>, Vec<String>) {
+        let pages = Rc::new(RefCell::new(Vec::new()));
+        let requested = Rc::new(RefCell::new(Vec::new()));
+        let having: HashSet<_> = history[with_metadata.min(history.len())..]
+            .iter()
+            .map(|c| c.sha.clone())
+            .collect();
+
+        let pages_cp = Rc::clone(&pages);
+        let commits = SyncApi(move |req: &CommitListRequest| {
+            pages_cp.borrow_mut().push(req.page);
+            let start = (req.page - 1) as usize * COMMITS_PAGE_SIZE as usize;
+            Ok(history
+                .iter()
+                .skip(start)
+                .take(COMMITS_PAGE_SIZE as usize)
+                .cloned()
+                .collect())
+        });
+        let compare = SyncApi(
+            |_: &CommitCompareRequest| -> Result<ComparePage, api::Error> {
+                Err(api::Error::Fetch("unexpected compare".to_string()))
+            },
+        );
+        let (requested_cp, having_cp) = (Rc::clone(&requested), having.clone());
+        let metadata = SyncApi(move |req: &CommitMetadataRequest| {
+            requested_cp.borrow_mut().push(req.commit.clone());
+            if having_cp.contains(&req.commit) {
+                Ok(metadata())
+            } else {
+                Err(api::Error::Http(StatusCode::NOT_FOUND))
+            }
+        });
+        let manifest = SyncApi(move |_: &ManifestRequest| {
+            if manifest {
+                let files = vec![("file.json".to_string(), having.clone())];
+                Ok(Manifest::new(files.into_iter().collect()))
+            } else {
+                Err(api::Error::Http(StatusCode::NOT_FOUND))
+            }
+        });
+        let tags = SyncApi(|_: &TagListRequest| {
+            let tag = |name: &str, commit: &str| Tag {
+                name: name.to_string(),
+                commit: commit.to_string(),
+            };
+            Ok(vec![tag("v1", &sha(PAGE + 3)), tag("v0", &sha(PAGE + 7))])
+        });
+        let apis = Apis {
+            commits: Rc::new(RefCell::new(commits)),
+            compare: Rc::new(RefCell::new(compare)),
+            metadata: Rc::new(RefCell::new(metadata)),
+            manifest: Rc::new(RefCell::new(manifest)),
+            tags: Rc::new(RefCell::new(tags)),
+        };
+
+        let list = Rc::new(RefCell::new(None));
+        let list_cp = Rc::clone(&list);
+        CommitDataSet::list_range(apis.clone(), "owner/repo", range.clone(), move |resp| {
+            list_cp.replace(Some(resp.expect("commits should be listed")));
+        });
+        let list = list.take().expect("commits should be listed synchronously");
+        let (delivered, callback) = recorder();
+        let file = "file.json".to_string();
+        CommitDataSet::collect_metadata(&apis, file, range, &list, callback);
+        let tags = Rc::new(RefCell::new(HashMap::new()));
+        let tags_cp = Rc::clone(&tags);
+        CommitDataSet::list_tags(&apis, "owner/repo", move |tags| {
+            tags_cp.replace(tags);
+        });
+        let tags = tags.take();
+        let delivered = delivered
+            .take()
+            .iter()
+            .map(|ds| ds.with_tags(&tags))
+            .collect();
+        let pages = pages.take();
+        let requested = requested.take();
+        (delivered, pages, requested)
+    }
+
+    fn skipping_missing(count: u32, samples: u32) -> Range {
+        Range {
+            skip_missing: true,
+            ..Range::new(None, count, samples)
+        }
+    }
+
+    fn sorted_keys<V>(metadata: &HashMap<String, V>) -> Vec<&str> {
+        let mut keys: Vec<_> = metadata.keys().map(|k| k.as_str()).collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_metadata_delivered_progressively() {
+        let api = Rc::new(RefCell::new(DeferredApi::default()));
+        let (delivered, callback) = recorder();
+        let commits = history(25);
+        CommitDataSet::request_commits_metadata(
+            "file.json".to_string(),
+            Rc::clone(&api),
+            callback,
+            commits,
+        );
+
+        // Listed commits are shown before any response.
+        assert_eq!(delivered.borrow().len(), 1);
+        assert_eq!(delivered.borrow()[0].loaded, 0);
+        assert_eq!(delivered.borrow()[0].total, 25);
+        assert!(delivered.borrow()[0].metadata.is_empty());
+
+        respond_all(&api);
+        let delivered = delivered.borrow();
+        let loaded: Vec<_> = delivered.iter().map(|ds| ds.loaded).collect();
+        // Every 25 / PROGRESS_STEPS responses, and the last one.
+        let mut expected = vec![0];
+        expected.extend((2..25).step_by(2));
+        expected.push(25);
+        assert_eq!(loaded, expected);
+        let last = delivered.last().unwrap();
+        assert!(last.is_complete());
+        assert_eq!(last.commits.len(), 25);
+        assert_eq!(last.metadata.len(), 25);
+        assert!(delivered[..delivered.len() - 1]
+            .iter()
+            .all(|ds| !ds.is_complete()));
+    }
+
+    #[test]
+    fn test_metadata_delivered_for_few_commits() {
+        let api = Rc::new(RefCell::new(DeferredApi::default()));
+        let (delivered, callback) = recorder();
+        CommitDataSet::request_commits_metadata(
+            "file.json".to_string(),
+            Rc::clone(&api),
+            callback,
+            history(3),
+        );
+        respond_all(&api);
+        let loaded: Vec<_> = delivered.borrow().iter().map(|ds| ds.loaded).collect();
+        assert_eq!(loaded, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_metadata_delivered_for_synchronous_responses() {
+        let api = SyncApi(|req: &CommitMetadataRequest| {
+            if req.commit == sha(1) {
+                Err(api::Error::Http(StatusCode::NOT_FOUND))
+            } else {
+                Ok(metadata())
+            }
+        });
+        let (delivered, callback) = recorder();
+        let api = Rc::new(RefCell::new(api));
+        CommitDataSet::request_commits_metadata("file.json".to_string(), api, callback, history(3));
+
+        // Responses arriving before the call returns are counted once each, and delivered
+        // together once all requests are issued.
+        let delivered = delivered.borrow();
+        let loaded: Vec<_> = delivered.iter().map(|ds| ds.loaded).collect();
+        assert_eq!(loaded, vec![0, 3]);
+        let last = delivered.last().unwrap();
+        assert!(last.is_complete());
+        assert_eq!(sorted_keys(&last.metadata), vec![sha(0), sha(2)]);
+    }
+
+    /// Request metadata of the commits through the API, returning what's delivered.
+    fn request<M>(api: M, commits: Vec<CommitInfo>) -> Vec<Result<CommitDataSet, api::Error>>
+    where
+        M: Api<CommitMetadataRequest, Rcvar> + 'static,
+    {
+        let delivered = Rc::new(RefCell::new(Vec::new()));
+        let delivered_cp = Rc::clone(&delivered);
+        let callback = move |resp| delivered_cp.borrow_mut().push(resp);
+        let api = Rc::new(RefCell::new(api));
+        CommitDataSet::request_commits_metadata("file.json".to_string(), api, callback, commits);
+        delivered.take()
+    }
+
+    #[test]
+    fn test_metadata_call_failure_delivers_error() {
+        struct FailingApi;
+        impl Api<CommitMetadataRequest, Rcvar> for FailingApi {
+            fn call<C>(
+                &mut self,
+                _: &CommitMetadataRequest,
+                _: C,
+            ) -> Result<Option<FetchTask>, anyhow::Error>
+            where
+                C: FnOnce(Result<Rcvar, api::Error>) + 'static,
+            {
+                Err(anyhow::anyhow!("no network"))
+            }
+        }
+
+        let delivered = request(FailingApi, history(2));
+        assert_eq!(delivered.len(), 2);
+        assert!(matches!(&delivered[0], Ok(ds) if ds.loaded == 0));
+        assert!(matches!(&delivered[1], Err(api::Error::Fetch(_))));
+    }
+
+    #[test]
+    fn test_metadata_error_when_all_fail() {
+        let api = SyncApi(|_: &CommitMetadataRequest| {
+            Err(api::Error::Http(StatusCode::INTERNAL_SERVER_ERROR))
+        });
+        let delivered = request(api, history(3));
+        let last = delivered.last().unwrap();
+        assert!(matches!(
+            last,
+            Err(api::Error::Http(StatusCode::INTERNAL_SERVER_ERROR))
+        ));
+    }
+
+    #[test]
+    fn test_metadata_not_found_is_not_error() {
+        let api = SyncApi(|_: &CommitMetadataRequest| Err(api::Error::Http(StatusCode::NOT_FOUND)));
+        let delivered = request(api, history(3));
+        let last = delivered.last().unwrap().as_ref().unwrap();
+        assert!(last.is_complete());
+        assert!(last.metadata.is_empty());
+        assert_eq!(last.failures.get(&sha(0)), Some(&Status::NotFound));
+    }
+
+    #[test]
+    fn test_metadata_partially_failed() {
+        let api = SyncApi(|req: &CommitMetadataRequest| {
+            if req.commit == sha(0) {
+                Err(api::Error::Fetch("connection reset".to_string()))
+            } else {
+                Ok(metadata())
+            }
+        });
+        let delivered = request(api, history(3));
+        let last = delivered.last().unwrap().as_ref().unwrap();
+        assert!(last.is_complete());
+        assert_eq!(sorted_keys(&last.metadata), vec![sha(1), sha(2)]);
+        assert_eq!(sorted_keys(&last.failures), vec![sha(0)]);
+        assert!(matches!(last.failures[&sha(0)], Status::FetchError(_)));
+    }
+
+    #[test]
+    fn test_probe_extends_range_over_pages() {
+        let history = history(PAGE + 10);
+        let (delivered, pages, requested) =
+            collect(history, PAGE + 2, false, skipping_missing(3, 3));
+        let last = delivered.last().unwrap();
+        assert!(last.is_complete());
+        assert_eq!(shas(&last.commits), shas_from(PAGE + 2, 3));
+        assert_eq!(sorted_keys(&last.metadata), shas_from(PAGE + 2, 3));
+        // Tags on older commits in the extended range are carried to the oldest one shown.
+        let mut expected = HashMap::new();
+        expected.insert(sha(PAGE + 3), vec!["v1".to_string()]);
+        expected.insert(sha(PAGE + 4), vec!["v0".to_string()]);
+        assert_eq!(last.tags, expected);
+        // The listed page is used up before listing the next one.
+        assert_eq!(pages, vec![1, 2]);
+        // Each commit is probed once, through the whole history.
+        let unique: HashSet<_> = requested.iter().collect();
+        assert_eq!(requested.len(), PAGE + 10);
+        assert_eq!(unique.len(), PAGE + 10);
+    }
+
+    #[test]
+    fn test_probe_stops_at_end_of_history() {
+        let (delivered, pages, requested) = collect(history(10), 10, false, skipping_missing(3, 3));
+        let last = delivered.last().unwrap();
+        assert!(last.is_complete());
+        assert!(last.commits.is_empty());
+        assert_eq!(pages, vec![1]);
+        assert_eq!(requested.len(), 10);
+    }
+
+    #[test]
+    fn test_probe_within_listed_page() {
+        let history = history(PAGE + 10);
+        let (delivered, pages, requested) = collect(history, 1, false, skipping_missing(3, 3));
+        let last = delivered.last().unwrap();
+        // Commits found beyond the count are sampled up to the count.
+        assert_eq!(shas(&last.commits), shas_from(1, 3));
+        // Enough commits were found among those listed already.
+        assert_eq!(pages, vec![1]);
+        assert_eq!(requested.len(), PAGE);
+    }
+
+    #[test]
+    fn test_manifest_extends_range_over_pages() {
+        let history = history(PAGE + 10);
+        let (delivered, pages, requested) =
+            collect(history, PAGE + 2, true, skipping_missing(3, 3));
+        let last = delivered.last().unwrap();
+        assert!(last.is_complete());
+        assert_eq!(shas(&last.commits), shas_from(PAGE + 2, 3));
+        assert_eq!(pages, vec![1, 2]);
+        // Only commits in the manifest are requested.
+        assert_eq!(requested, shas_from(PAGE + 2, 3));
+    }
+
+    /// Returns the timestamp a day away from that of all commits in tests.
+    fn day_from_commits(later: bool) -> chrono::DateTime<chrono::Utc> {
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        if later {
+            (SystemTime::UNIX_EPOCH + day).into()
+        } else {
+            (SystemTime::UNIX_EPOCH - day).into()
+        }
+    }
+
+    #[test]
+    fn test_window_listing_truncated() {
+        let range = Range {
+            since: Some(day_from_commits(false)),
+            ..Range::new(None, 10, 10)
+        };
+        let max = MAX_LISTED_PAGES as usize * PAGE;
+        let (delivered, pages, _) = collect(history(max + 10), 0, false, range.clone());
+        assert!(delivered.last().unwrap().truncated);
+        assert_eq!(pages.len(), MAX_LISTED_PAGES as usize);
+
+        let (delivered, _, _) = collect(history(max - 10), 0, false, range);
+        assert!(!delivered.last().unwrap().truncated);
+    }
+
+    #[test]
+    fn test_until_only_range_bounded_by_count() {
+        let range = Range {
+            until: Some(day_from_commits(true)),
+            ..Range::new(None, 10, 10)
+        };
+        let (delivered, pages, _) = collect(history(3 * PAGE), 0, false, range);
+        let last = delivered.last().unwrap();
+        assert_eq!(shas(&last.commits), shas_from(0, 10));
+        assert!(!last.truncated);
+        // Only pages up to the count are listed, unlike a date window.
+        assert_eq!(pages, vec![1]);
+    }
+}
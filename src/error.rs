@@ -0,0 +1,33 @@
+use thiserror::Error;
+use wasm_bindgen::JsValue;
+
+/// Errors failing to create the app, which are thrown to the caller of `create` in JS.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid config: {0}")]
+    Config(#[from] serde_wasm_bindgen::Error),
+    #[error("invalid config:\n{}", .0.join("\n"))]
+    Invalid(Vec<String>),
+    #[error("invalid range: {0}")]
+    Range(serde_wasm_bindgen::Error),
+    #[error("invalid selector '{0}' in config.on")]
+    Selector(String),
+    #[error("no element matches selector '{0}' in config.on")]
+    NoElement(String),
+}
+
+impl Error {
+    /// Returns descriptions of each problem, to list them to users.
+    pub fn problems(&self) -> Vec<String> {
+        match self {
+            Error::Invalid(problems) => problems.clone(),
+            e => vec![e.to_string()],
+        }
+    }
+}
+
+impl From<Error> for JsValue {
+    fn from(e: Error) -> Self {
+        js_sys::Error::new(&e.to_string()).into()
+    }
+}
@@ -0,0 +1,260 @@
+use crate::config::{Axis, AxisPosition, Series, Unit, ValueFormat};
+use number_prefix::NumberPrefix;
+
+/// Units of time to format durations with, in seconds.
+const DURATION_UNITS: &[(f64, &str)] = &[
+    (3600.0, "h"),
+    (60.0, "min"),
+    (1.0, "s"),
+    (1e-3, "ms"),
+    (1e-6, "µs"),
+    (1e-9, "ns"),
+];
+
+/// Formats values of a chart into human-friendly strings, consistently between the axis,
+/// tooltips and the table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueFormatter {
+    format: ValueFormat,
+    suffix: String,
+    /// Multiplier to normalize values into the base unit of `format`.
+    scale: f64,
+}
+
+impl ValueFormatter {
+    pub fn new(unit: Option<&Unit>, axis: &Axis) -> Self {
+        let (format, suffix, scale) = match unit {
+            None => (
+                axis.format.unwrap_or_default(),
+                axis.unit.clone().unwrap_or_default(),
+                1.0,
+            ),
+            Some(Unit::Time { base }) => (ValueFormat::Duration, String::new(), base.seconds()),
+            Some(Unit::Bytes) => (ValueFormat::Binary, "B".to_string(), 1.0),
+            Some(Unit::Rate { label }) => (
+                ValueFormat::Decimal,
+                format!("{}/s", label.as_deref().unwrap_or("")),
+                1.0,
+            ),
+            Some(Unit::Percent { ratio }) => (
+                ValueFormat::Percent,
+                String::new(),
+                if *ratio { 1.0 } else { 0.01 },
+            ),
+            Some(Unit::Count { label }) => {
+                (ValueFormat::Decimal, label.clone().unwrap_or_default(), 1.0)
+            }
+        };
+        Self {
+            format,
+            suffix,
+            scale,
+        }
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        let value = value * self.scale;
+        match self.format {
+            ValueFormat::Decimal => with_prefix(NumberPrefix::decimal(value), &self.suffix),
+            ValueFormat::Binary => with_prefix(NumberPrefix::binary(value), &self.suffix),
+            ValueFormat::Percent => format!("{}%", round(value * 100.0)),
+            ValueFormat::Duration => duration(value),
+        }
+    }
+}
+
+/// Returns formatters of the main value of a chart followed by each of its extra series.
+pub fn series_formatters(
+    unit: Option<&Unit>,
+    axis: &Axis,
+    series: &[Series],
+    right_axis: &Axis,
+) -> Vec<ValueFormatter> {
+    let mut formatters = Vec::with_capacity(series.len() + 1);
+    formatters.push(ValueFormatter::new(unit, axis));
+    for s in series {
+        formatters.push(match s.y_axis {
+            AxisPosition::Left => ValueFormatter::new(s.unit.as_ref().or(unit), axis),
+            AxisPosition::Right => ValueFormatter::new(s.unit.as_ref(), right_axis),
+        });
+    }
+    formatters
+}
+
+fn with_prefix(prefixed: NumberPrefix<f64>, unit: &str) -> String {
+    let (n, prefix) = match prefixed {
+        NumberPrefix::Standalone(n) => (n, String::new()),
+        NumberPrefix::Prefixed(prefix, n) => (n, prefix.to_string()),
+    };
+    if prefix.is_empty() && unit.is_empty() {
+        round(n)
+    } else {
+        format!("{} {}{}", round(n), prefix, unit)
+    }
+}
+
+fn duration(seconds: f64) -> String {
+    if seconds == 0.0 {
+        return "0 s".to_string();
+    }
+    let (scale, unit) = DURATION_UNITS
+        .iter()
+        .find(|(scale, _)| seconds.abs() >= *scale)
+        .unwrap_or(&DURATION_UNITS[DURATION_UNITS.len() - 1]);
+    format!("{} {}", round(seconds / scale), unit)
+}
+
+/// Format a number with at most 2 fractional digits, dropping trailing zeros.
+fn round(n: f64) -> String {
+    let s = format!("{:.2}", n);
+    let s = if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &s
+    };
+    // Negative values rounded to zero aren't worth the sign.
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeUnit;
+
+    fn series(y_axis: AxisPosition, unit: Option<Unit>) -> Series {
+        Series {
+            title: "series".to_string(),
+            query: "value".to_string(),
+            y_axis,
+            unit,
+        }
+    }
+
+    fn formatter(unit: Unit) -> ValueFormatter {
+        ValueFormatter::new(Some(&unit), &Axis::default())
+    }
+
+    #[test]
+    fn test_durations_scale_up() {
+        let f = formatter(Unit::Time { base: TimeUnit::Ns });
+        assert_eq!(f.format(500.0), "500 ns");
+        assert_eq!(f.format(1_500_000.0), "1.5 ms");
+        assert_eq!(f.format(2e9), "2 s");
+        assert_eq!(f.format(90e9), "1.5 min");
+        assert_eq!(f.format(7200e9), "2 h");
+    }
+
+    #[test]
+    fn test_durations_scale_down() {
+        let f = formatter(Unit::Time { base: TimeUnit::S });
+        assert_eq!(f.format(0.25), "250 ms");
+        assert_eq!(f.format(3e-6), "3 µs");
+        assert_eq!(f.format(1e-12), "0 ns");
+    }
+
+    #[test]
+    fn test_bytes() {
+        let f = formatter(Unit::Bytes);
+        assert_eq!(f.format(512.0), "512 B");
+        assert_eq!(f.format(1536.0), "1.5 KiB");
+        assert_eq!(f.format(3.0 * 1024.0 * 1024.0), "3 MiB");
+    }
+
+    #[test]
+    fn test_percent() {
+        let f = formatter(Unit::Percent { ratio: true });
+        assert_eq!(f.format(0.255), "25.5%");
+        assert_eq!(f.format(1.0), "100%");
+        let f = formatter(Unit::Percent { ratio: false });
+        assert_eq!(f.format(25.5), "25.5%");
+    }
+
+    #[test]
+    fn test_rate_and_count() {
+        let f = formatter(Unit::Rate {
+            label: Some("ops".to_string()),
+        });
+        assert_eq!(f.format(1500.0), "1.5 kops/s");
+        let f = formatter(Unit::Count { label: None });
+        assert_eq!(f.format(42.0), "42");
+        assert_eq!(f.format(2_000_000.0), "2 M");
+    }
+
+    #[test]
+    fn test_zero() {
+        assert_eq!(
+            formatter(Unit::Time { base: TimeUnit::Ms }).format(0.0),
+            "0 s"
+        );
+        assert_eq!(formatter(Unit::Bytes).format(0.0), "0 B");
+        assert_eq!(formatter(Unit::Percent { ratio: true }).format(0.0), "0%");
+        assert_eq!(formatter(Unit::Count { label: None }).format(0.0), "0");
+    }
+
+    #[test]
+    fn test_negative() {
+        assert_eq!(
+            formatter(Unit::Time { base: TimeUnit::S }).format(-0.25),
+            "-250 ms"
+        );
+        assert_eq!(formatter(Unit::Bytes).format(-1536.0), "-1.5 KiB");
+        assert_eq!(
+            formatter(Unit::Percent { ratio: true }).format(-0.5),
+            "-50%"
+        );
+        assert_eq!(formatter(Unit::Count { label: None }).format(-3.0), "-3");
+    }
+
+    #[test]
+    fn test_axis_unit() {
+        let axis = Axis {
+            unit: Some("req".to_string()),
+            ..Axis::default()
+        };
+        let f = ValueFormatter::new(None, &axis);
+        assert_eq!(f.format(1.0), "1 req");
+        assert_eq!(f.format(2500.0), "2.5 kreq");
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(round(2.0), "2");
+        assert_eq!(round(1.5), "1.5");
+        assert_eq!(round(1.234), "1.23");
+        assert_eq!(round(10.0), "10");
+        assert_eq!(round(-1.25), "-1.25");
+        assert_eq!(round(0.001), "0");
+        assert_eq!(round(-0.001), "0");
+    }
+
+    #[test]
+    fn test_series_formatters() {
+        let right_axis = Axis {
+            unit: Some("req".to_string()),
+            ..Axis::default()
+        };
+        let series = [
+            series(AxisPosition::Left, None),
+            series(AxisPosition::Left, Some(Unit::Bytes)),
+            series(AxisPosition::Right, None),
+            series(AxisPosition::Right, Some(Unit::Percent { ratio: true })),
+        ];
+        let unit = Unit::Time { base: TimeUnit::Ms };
+        let formatters = series_formatters(Some(&unit), &Axis::default(), &series, &right_axis);
+        let formatted: Vec<_> = formatters.iter().map(|f| f.format(1500.0)).collect();
+        assert_eq!(
+            formatted,
+            ["1.5 s", "1.5 s", "1.46 KiB", "1.5 kreq", "150000%"]
+        );
+    }
+
+    #[test]
+    fn test_series_formatters_without_series() {
+        let formatters = series_formatters(None, &Axis::default(), &[], &Axis::default());
+        assert_eq!(formatters, [ValueFormatter::new(None, &Axis::default())]);
+    }
+}
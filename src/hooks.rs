@@ -0,0 +1,249 @@
+use crate::commit::{CommitInfo, UserInfo};
+use crate::component::CommitViewData;
+use crate::config::Series;
+use crate::range::Range;
+use chrono::{DateTime, Utc};
+use js_sys::{Function, Object, Reflect};
+use log::*;
+use serde::Serialize;
+use std::time::SystemTime;
+use wasm_bindgen::{JsCast, JsValue};
+
+const HOOKS_KEY: &str = "hooks";
+
+/// Functions given in `hooks` of the JS config, which are called on events of the dashboard.
+/// They can't be deserialized along with the rest of the config, so are taken out by `split`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hooks {
+    /// Called with the datapoint clicked, in place of opening the page of the commit.
+    pub point_click: Option<Function>,
+    /// Called with the datapoint hovered.
+    pub point_hover: Option<Function>,
+    /// Called with the new range when it's changed.
+    pub range_change: Option<Function>,
+    /// Called with all datapoints of a chart once its data are loaded.
+    pub data_loaded: Option<Function>,
+}
+
+impl Hooks {
+    /// Take hooks out of the JS config, returning the rest of the config along with hooks or
+    /// problems of them.
+    pub fn split(config: JsValue) -> (JsValue, Result<Self, Vec<String>>) {
+        let key = JsValue::from_str(HOOKS_KEY);
+        let hooks = match Reflect::get(&config, &key) {
+            Ok(hooks) if config.is_object() && !hooks.is_undefined() => hooks,
+            _ => return (config, Ok(Self::default())),
+        };
+        // Leave the config given by the caller as is.
+        let rest = Object::assign(&Object::new(), config.unchecked_ref::<Object>());
+        let _ = Reflect::delete_property(&rest, &key);
+        (rest.into(), Self::parse(&hooks))
+    }
+
+    fn parse(hooks: &JsValue) -> Result<Self, Vec<String>> {
+        let hooks = match hooks.dyn_ref::<Object>() {
+            Some(hooks) if !hooks.is_function() => hooks,
+            _ => {
+                return Err(vec![format!(
+                    "{}: expected an object of functions",
+                    HOOKS_KEY
+                )])
+            }
+        };
+        let mut parsed = Self::default();
+        let mut problems = Vec::new();
+        for key in Object::keys(hooks).iter() {
+            let name = key.as_string().unwrap_or_default();
+            let slot = match name.as_str() {
+                "point_click" => &mut parsed.point_click,
+                "point_hover" => &mut parsed.point_hover,
+                "range_change" => &mut parsed.range_change,
+                "data_loaded" => &mut parsed.data_loaded,
+                _ => {
+                    problems.push(format!("{}.{}: unknown hook", HOOKS_KEY, name));
+                    continue;
+                }
+            };
+            match Reflect::get(hooks, &key).map(JsCast::dyn_into::<Function>) {
+                Ok(Ok(function)) => *slot = Some(function),
+                _ => problems.push(format!("{}.{}: expected a function", HOOKS_KEY, name)),
+            }
+        }
+        if problems.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Call `point_click` with the datapoint made by `Point::to_value`, returning false if it
+    /// isn't set to fall back to the default behavior.
+    pub fn point_click(&self, point: &JsValue) -> bool {
+        call_with("point_click", &self.point_click, point)
+    }
+
+    pub fn point_hover(&self, point: &JsValue) {
+        call_with("point_hover", &self.point_hover, point);
+    }
+
+    pub fn range_change(&self, range: &Range) {
+        call("range_change", &self.range_change, &RangeInfo::new(range));
+    }
+
+    pub fn data_loaded(&self, title: &str, series: &[Series], data: &CommitViewData) {
+        if self.data_loaded.is_none() {
+            return;
+        }
+        let loaded = DataLoaded {
+            chart: title,
+            points: data
+                .commits
+                .iter()
+                .map(|commit| Point::new(title, series, data, commit))
+                .collect(),
+        };
+        call("data_loaded", &self.data_loaded, &loaded);
+    }
+}
+
+/// Call the hook with the argument converted to a JS value, returning true if it's set.
+/// Exceptions thrown by the hook are logged without affecting the dashboard.
+fn call<T: Serialize>(name: &str, hook: &Option<Function>, arg: &T) -> bool {
+    if hook.is_none() {
+        return false;
+    }
+    match serde_wasm_bindgen::to_value(arg) {
+        Ok(arg) => call_with(name, hook, &arg),
+        Err(e) => {
+            error!("Failed to convert argument of hook {}: {}", name, e);
+            true
+        }
+    }
+}
+
+fn call_with(name: &str, hook: &Option<Function>, arg: &JsValue) -> bool {
+    let hook = match hook {
+        Some(hook) => hook,
+        None => return false,
+    };
+    if let Err(e) = hook.call1(&JsValue::NULL, arg) {
+        error!("Error in hook {}: {:?}", name, e);
+    }
+    true
+}
+
+fn iso_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+/// Datapoint of a commit passed to hooks.
+#[derive(Debug, Serialize)]
+pub struct Point<'a> {
+    /// Title of the chart having the datapoint
+    chart: &'a str,
+    commit: Commit<'a>,
+    /// Value of the chart, which is undefined if missing
+    value: Option<f64>,
+    /// Values of extra series in the configured order
+    series: Vec<SeriesValue<'a>>,
+}
+
+impl<'a> Point<'a> {
+    pub fn new(
+        title: &'a str,
+        series: &'a [Series],
+        data: &CommitViewData,
+        commit: &'a CommitInfo,
+    ) -> Self {
+        let value_of = |index| {
+            data.values(index)
+                .and_then(|values| values.get(&commit.sha))
+                .copied()
+        };
+        Self {
+            chart: title,
+            commit: Commit::new(commit),
+            value: value_of(0),
+            series: series
+                .iter()
+                .enumerate()
+                .map(|(i, s)| SeriesValue {
+                    title: &s.title,
+                    value: value_of(i + 1),
+                })
+                .collect(),
+        }
+    }
+
+    /// Convert to the JS value passed to hooks. Charts do so before calling hooks to release
+    /// their state, as hooks might update the chart synchronously through the handle.
+    pub fn to_value(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or_else(|e| {
+            error!("Failed to convert datapoint for hooks: {}", e);
+            JsValue::UNDEFINED
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SeriesValue<'a> {
+    title: &'a str,
+    value: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Commit<'a> {
+    sha: &'a str,
+    message: &'a str,
+    url: &'a str,
+    author: &'a UserInfo,
+    author_date: String,
+    committer: &'a UserInfo,
+    commit_date: String,
+    parents: &'a [String],
+    pull_request: Option<u32>,
+}
+
+impl<'a> Commit<'a> {
+    fn new(commit: &'a CommitInfo) -> Self {
+        Self {
+            sha: &commit.sha,
+            message: &commit.message,
+            url: &commit.view_url,
+            author: &commit.author,
+            author_date: iso_date(commit.author_date),
+            committer: &commit.committer,
+            commit_date: iso_date(commit.commit_date),
+            parents: &commit.parents,
+            pull_request: commit.merged_pull_request(),
+        }
+    }
+}
+
+/// Range passed to `range_change`, in the same shape as the argument of `setRange`.
+#[derive(Debug, Serialize)]
+struct RangeInfo<'a> {
+    count: u32,
+    since: Option<String>,
+    until: Option<String>,
+    base: Option<&'a str>,
+    head: Option<&'a str>,
+}
+
+impl<'a> RangeInfo<'a> {
+    fn new(range: &'a Range) -> Self {
+        Self {
+            count: range.count,
+            since: range.since.map(|t| t.to_rfc3339()),
+            until: range.until.map(|t| t.to_rfc3339()),
+            base: range.base.as_deref(),
+            head: range.from.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DataLoaded<'a> {
+    chart: &'a str,
+    points: Vec<Point<'a>>,
+}
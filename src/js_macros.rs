@@ -2,8 +2,10 @@
 macro_rules! closure {
     ($store:expr, $type:ty, $fn:expr) => {{
         let closure = Closure::wrap(Box::new($fn) as Box<$type>);
-        $store.push(Box::new(closure) as Box<dyn Drop>);
-        unsafe { &**($store.last().unwrap() as *const Box<dyn Drop> as *const Box<Closure<$type>>) }
+        $store.push(Box::new(closure) as Box<dyn std::any::Any>);
+        unsafe {
+            &*(&**$store.last().unwrap() as *const dyn std::any::Any as *const Closure<$type>)
+        }
     }};
 }
 
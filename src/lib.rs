@@ -1,5 +1,6 @@
 #![recursion_limit = "512"]
 
+#[cfg(feature = "chartjs")]
 #[macro_use]
 mod js_macros;
 mod api;
@@ -9,10 +10,18 @@ mod commit;
 mod component;
 mod config;
 mod dataset;
+mod error;
+mod format;
+mod hooks;
+mod overlay;
 mod query;
 mod range;
+mod sampling;
+mod store;
 
+use error::Error;
 use wasm_bindgen::prelude::*;
+use yew::Callback;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -20,17 +29,153 @@ use wasm_bindgen::prelude::*;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+/// Handle of the dashboard returned from `create`, to control it from JS.
 #[wasm_bindgen]
-pub fn create(config: JsValue) -> Result<(), JsValue> {
+pub struct Loupe {
+    send: Callback<component::loupe::Msg>,
+    on: String,
+    renderer: config::Renderer,
+}
+
+#[wasm_bindgen]
+impl Loupe {
+    /// Replace the config of the dashboard, which is validated in the same way as `create`.
+    /// `on` and `renderer` can't be changed, for which the dashboard needs to be created again.
+    #[wasm_bindgen(js_name = setConfig)]
+    pub fn set_config(&self, config: JsValue) -> Result<(), JsValue> {
+        let (config, hooks) = parse_config(config)?;
+        if config.on != self.on || config.renderer != self.renderer {
+            log::warn!("Changes to on and renderer are ignored by setConfig");
+        }
+        self.send
+            .emit(component::loupe::Msg::SetConfig(Box::new(config), hooks));
+        Ok(())
+    }
+
+    /// Fetch everything again, to show commits and data pushed after the dashboard is created.
+    pub fn refresh(&self) {
+        self.send.emit(component::loupe::Msg::Refresh);
+    }
+
+    /// Set the range of commits to show, given as an object having any of `count`, `since`,
+    /// `until`, `base` and `head`. Omitted ones are reset to those of the config, e.g. `head`
+    /// to `branch`, rather than kept from the current range.
+    #[wasm_bindgen(js_name = setRange)]
+    pub fn set_range(&self, range: JsValue) -> Result<(), JsValue> {
+        let spec = serde_wasm_bindgen::from_value(range).map_err(Error::Range)?;
+        self.send.emit(component::loupe::Msg::SetRange(spec));
+        Ok(())
+    }
+
+    /// Unmount the dashboard, releasing charts. The handle can't be used after this.
+    pub fn destroy(&self) {
+        self.send.emit(component::loupe::Msg::Destroy);
+    }
+}
+
+#[wasm_bindgen]
+pub fn create(config: JsValue) -> Result<Loupe, JsValue> {
     wasm_logger::init(wasm_logger::Config::default());
     yew::initialize();
-    let config: config::Config = serde_wasm_bindgen::from_value(config)?;
-    let elem = yew::utils::document()
-        .query_selector(&config.on)
-        .unwrap()
-        .unwrap();
-    let props = component::loupe::Properties { config };
-    yew::App::<component::loupe::LoupeComponent<chart::chartjs::ChartJs>>::new()
-        .mount_with_props(elem, props);
-    Ok(())
+    try_create(config).map_err(Into::into)
+}
+
+fn try_create(config: JsValue) -> Result<Loupe, Error> {
+    // Read the target ahead of parsing the config, to show problems of the config in it.
+    let on = js_sys::Reflect::get(&config, &JsValue::from_str("on"))
+        .ok()
+        .and_then(|on| on.as_string());
+    let (config, hooks) = match parse_config(config) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            if let Some(elem) = on.and_then(|on| find_element(&on).ok()) {
+                show_problems(&elem, &e.problems());
+            }
+            return Err(e);
+        }
+    };
+    let elem = find_element(&config.on)?;
+    let on = config.on.clone();
+    let renderer = config.renderer;
+    let props = component::loupe::Properties { config, hooks };
+    let send = match renderer {
+        #[cfg(feature = "chartjs")]
+        config::Renderer::ChartJs => mount::<chart::chartjs::ChartJs>(elem, props),
+        #[cfg(not(feature = "chartjs"))]
+        config::Renderer::ChartJs => {
+            log::warn!("Built without chartjs feature, falling back to SVG renderer");
+            mount::<chart::svg::SvgChart>(elem, props)
+        }
+        config::Renderer::Svg => mount::<chart::svg::SvgChart>(elem, props),
+    };
+    Ok(Loupe { send, on, renderer })
+}
+
+fn parse_config(config: JsValue) -> Result<(config::Config, hooks::Hooks), Error> {
+    let (config, hooks) = hooks::Hooks::split(config);
+    let deserializer = serde_wasm_bindgen::Deserializer::from(config);
+    let (config, unknown) = match (config::Config::parse(deserializer), &hooks) {
+        (Ok(parsed), _) => parsed,
+        (Err(e), Ok(_)) => return Err(e.into()),
+        // The rest can't be validated without the parsed config, but problems of hooks can be.
+        (Err(e), Err(hook_problems)) => {
+            let mut problems = vec![e.to_string()];
+            problems.extend(hook_problems.iter().cloned());
+            return Err(Error::Invalid(problems));
+        }
+    };
+    let mut problems: Vec<_> = unknown
+        .into_iter()
+        .map(|at| format!("{}: unknown field", at))
+        .collect();
+    problems.extend(config.validate());
+    let hooks = hooks.unwrap_or_else(|hook_problems| {
+        problems.extend(hook_problems);
+        hooks::Hooks::default()
+    });
+    if !problems.is_empty() {
+        return Err(Error::Invalid(problems));
+    }
+    Ok((config, hooks))
+}
+
+fn find_element(selector: &str) -> Result<web_sys::Element, Error> {
+    yew::utils::document()
+        .query_selector(selector)
+        .map_err(|_| Error::Selector(selector.to_owned()))?
+        .ok_or_else(|| Error::NoElement(selector.to_owned()))
+}
+
+/// Render the list of problems into the element in place of charts.
+fn show_problems(elem: &web_sys::Element, problems: &[String]) {
+    let document = yew::utils::document();
+    let render = || -> Result<(), JsValue> {
+        let container = document.create_element("div")?;
+        container.set_class_name("loupe-config-errors");
+        let heading = document.create_element("p")?;
+        heading.set_text_content(Some("commits-loupe: invalid config"));
+        container.append_child(&heading)?;
+        let list = document.create_element("ul")?;
+        for problem in problems {
+            let item = document.create_element("li")?;
+            item.set_text_content(Some(problem));
+            list.append_child(&item)?;
+        }
+        container.append_child(&list)?;
+        elem.append_child(&container)?;
+        Ok(())
+    };
+    if let Err(e) = render() {
+        log::error!("Failed to show problems of config: {:?}", e);
+    }
+}
+
+/// Mount the app rendering charts by `C`, returning the callback to send messages to it.
+fn mount<C: chart::Chart + 'static>(
+    elem: web_sys::Element,
+    props: component::loupe::Properties,
+) -> Callback<component::loupe::Msg> {
+    yew::App::<component::loupe::LoupeComponent<C>>::new()
+        .mount_with_props(elem, props)
+        .callback(|msg| msg)
 }
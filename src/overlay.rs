@@ -0,0 +1,171 @@
+use crate::config::Overlay;
+
+/// Compute values of the overlay line from values of datapoints in chronological order, with
+/// their positions on X axis. Missing datapoints are skipped for the moving averages and the
+/// trend is drawn through all.
+pub fn compute(overlay: &Overlay, xs: &[f64], values: &[Option<f64>]) -> Vec<Option<f64>> {
+    match *overlay {
+        Overlay::Sma { window } => simple_moving_average(values, window.max(1)),
+        Overlay::Ema { window } => exponential_moving_average(values, window.max(1)),
+        Overlay::Trend => linear_trend(xs, values),
+    }
+}
+
+fn simple_moving_average(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    let mut recent = Vec::with_capacity(window);
+    values
+        .iter()
+        .map(|value| {
+            let v = (*value)?;
+            if recent.len() == window {
+                recent.remove(0);
+            }
+            recent.push(v);
+            Some(recent.iter().sum::<f64>() / recent.len() as f64)
+        })
+        .collect()
+}
+
+fn exponential_moving_average(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    let alpha = 2.0 / (window as f64 + 1.0);
+    let mut average: Option<f64> = None;
+    values
+        .iter()
+        .map(|value| {
+            let v = (*value)?;
+            let next = match average {
+                Some(avg) => alpha * v + (1.0 - alpha) * avg,
+                None => v,
+            };
+            average = Some(next);
+            average
+        })
+        .collect()
+}
+
+fn linear_trend(xs: &[f64], values: &[Option<f64>]) -> Vec<Option<f64>> {
+    let points: Vec<_> = xs
+        .iter()
+        .zip(values)
+        .filter_map(|(x, v)| v.map(|v| (*x, v)))
+        .collect();
+    if points.len() < 2 {
+        return vec![None; values.len()];
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    // Datapoints all at the same X, e.g., of commits made at once on time scale, have no slope.
+    let slope = if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    };
+    let intercept = mean_y - slope * mean_x;
+    xs.iter().map(|x| Some(intercept + slope * x)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xs(n: usize) -> Vec<f64> {
+        (0..n).map(|i| i as f64).collect()
+    }
+
+    fn assert_close(actual: &[Option<f64>], expected: &[Option<f64>]) {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            match (a, e) {
+                (Some(a), Some(e)) => {
+                    assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected)
+                }
+                _ => assert_eq!(a, e, "{:?} != {:?}", actual, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_values() {
+        for overlay in &[
+            Overlay::Sma { window: 3 },
+            Overlay::Ema { window: 3 },
+            Overlay::Trend,
+        ] {
+            assert!(compute(overlay, &[], &[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_all_missing_values() {
+        let values = vec![None; 3];
+        for overlay in &[
+            Overlay::Sma { window: 3 },
+            Overlay::Ema { window: 3 },
+            Overlay::Trend,
+        ] {
+            assert_eq!(compute(overlay, &xs(3), &values), values);
+        }
+    }
+
+    #[test]
+    fn test_sma() {
+        let values = [Some(1.0), Some(2.0), None, Some(3.0), Some(7.0)];
+        let sma = compute(&Overlay::Sma { window: 2 }, &xs(5), &values);
+        assert_close(&sma, &[Some(1.0), Some(1.5), None, Some(2.5), Some(5.0)]);
+    }
+
+    #[test]
+    fn test_sma_with_large_window() {
+        let values = [Some(1.0), Some(2.0), Some(6.0)];
+        let sma = compute(&Overlay::Sma { window: 10 }, &xs(3), &values);
+        assert_close(&sma, &[Some(1.0), Some(1.5), Some(3.0)]);
+    }
+
+    #[test]
+    fn test_zero_window_is_one() {
+        let values = [Some(1.0), None, Some(3.0)];
+        for overlay in &[Overlay::Sma { window: 0 }, Overlay::Ema { window: 0 }] {
+            assert_close(&compute(overlay, &xs(3), &values), &values);
+        }
+    }
+
+    #[test]
+    fn test_ema() {
+        // alpha = 2 / (3 + 1) = 0.5
+        let values = [Some(4.0), None, Some(8.0), Some(2.0)];
+        let ema = compute(&Overlay::Ema { window: 3 }, &xs(4), &values);
+        assert_close(&ema, &[Some(4.0), None, Some(6.0), Some(4.0)]);
+    }
+
+    #[test]
+    fn test_trend() {
+        let values = [Some(1.0), None, Some(5.0), Some(7.0)];
+        let trend = compute(&Overlay::Trend, &xs(4), &values);
+        assert_close(&trend, &[Some(1.0), Some(3.0), Some(5.0), Some(7.0)]);
+    }
+
+    #[test]
+    fn test_trend_of_single_value() {
+        let values = [None, Some(1.0), None];
+        assert_eq!(compute(&Overlay::Trend, &xs(3), &values), vec![None; 3]);
+    }
+
+    #[test]
+    fn test_trend_at_same_x() {
+        let values = [Some(1.0), Some(3.0)];
+        let trend = compute(&Overlay::Trend, &[5.0, 5.0], &values);
+        assert_close(&trend, &[Some(2.0), Some(2.0)]);
+    }
+}
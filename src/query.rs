@@ -1,11 +1,14 @@
+use jmespatch::Rcvar;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("malformed json: {0}")]
-    MalformedJsonError(String),
+    MalformedJson(String),
     #[error("jmespath error: {0}")]
     Jmespath(#[from] jmespatch::JmespathError),
+    #[error("query returned non-numeric value: {0}")]
+    NonNumeric(String),
 }
 
 pub struct Query {
@@ -18,8 +21,16 @@ impl Query {
         Ok(Self { expr })
     }
 
-    pub fn extract_value(&self, json: &str) -> Result<Option<f64>, Error> {
-        let var = jmespatch::Variable::from_json(&json).map_err(Error::MalformedJsonError)?;
-        Ok(self.expr.search(var)?.as_number())
+    pub fn extract_value(&self, var: &Rcvar) -> Result<f64, Error> {
+        let found = self.expr.search(var)?;
+        found
+            .as_number()
+            .ok_or_else(|| Error::NonNumeric(found.to_string()))
     }
 }
+
+/// Parse metadata JSON, which is done once per file and commit to run any number of queries on.
+pub fn parse_json(json: &str) -> Result<Rcvar, Error> {
+    let var = jmespatch::Variable::from_json(json).map_err(Error::MalformedJson)?;
+    Ok(Rcvar::new(var))
+}
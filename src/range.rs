@@ -1,11 +1,67 @@
-use crate::commit::CommitInfo;
+use crate::commit::{self, CommitInfo};
+use crate::config::Sampling;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
 use log::*;
+use serde::{de, Deserialize, Deserializer};
+use std::time::SystemTime;
+
+const DATE_INPUT_FORMAT: &str = "%Y-%m-%d";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Range {
     pub from: Option<String>,
     pub count: u32,
     pub samples: u32,
+    /// Only commits after this timestamp are listed when set.
+    pub since: Option<DateTime<Utc>>,
+    /// Only commits before this timestamp are listed when set.
+    pub until: Option<DateTime<Utc>>,
+    /// When set, commits between this ref and `from` are listed instead, ignoring other bounds.
+    pub base: Option<String>,
+    pub sampling: Sampling,
+    /// Sample only commits which have metadata.
+    pub skip_missing: bool,
+    /// Follow only the first parent of merge commits.
+    pub first_parent: bool,
+}
+
+/// Bounds of a range given through the JS API, each of which overrides that of the range made from
+/// the config. Dates are either `YYYY-MM-DD` in local timezone or RFC 3339 timestamps.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RangeSpec {
+    /// Number of the latest commits to show.
+    pub count: Option<u32>,
+    #[serde(deserialize_with = "deserialize_since")]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(deserialize_with = "deserialize_until")]
+    pub until: Option<DateTime<Utc>>,
+    pub base: Option<String>,
+    pub head: Option<String>,
+}
+
+fn deserialize_since<'de, D: Deserializer<'de>>(d: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+    deserialize_date(d, false)
+}
+
+fn deserialize_until<'de, D: Deserializer<'de>>(d: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+    deserialize_date(d, true)
+}
+
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    d: D,
+    end_of_day: bool,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    let value = match Option::<String>::deserialize(d)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    DateTime::parse_from_rfc3339(&value)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| parse_date_input(&value, end_of_day))
+        .map(Some)
+        .ok_or_else(|| de::Error::custom(format!("invalid date: {}", value)))
 }
 
 impl Range {
@@ -14,15 +70,94 @@ impl Range {
             from,
             count,
             samples,
+            since: None,
+            until: None,
+            base: None,
+            sampling: Sampling::Stride,
+            skip_missing: false,
+            first_parent: false,
         }
     }
 
+    /// Returns the head ref to compare against `base`.
+    pub fn head(&self) -> &str {
+        self.from.as_deref().unwrap_or("HEAD")
+    }
+
+    /// Returns true if this range is bounded by date rather than by count of commits. A range
+    /// having only `until` is bounded by count, to the latest commits until then.
+    pub fn is_windowed(&self) -> bool {
+        self.since.is_some()
+    }
+
     pub fn pages_for_batch(&self, batch_size: u32) -> u32 {
         (self.count as f64 / batch_size as f64).ceil() as u32
     }
 
+    /// Returns true if the number of commits in this range is bounded by `count`.
+    fn is_count_bounded(&self) -> bool {
+        !self.is_windowed() && self.base.is_none()
+    }
+
+    fn is_in_window(&self, commit: &CommitInfo) -> bool {
+        let time = commit.commit_date;
+        self.since
+            .map(|t| time >= SystemTime::from(t))
+            .unwrap_or(true)
+            && self
+                .until
+                .map(|t| time <= SystemTime::from(t))
+                .unwrap_or(true)
+    }
+
+    /// Drop commits listed beyond the count or out of the date window of this range, or those off
+    /// the first-parent history.
+    pub fn filter(&self, commits: &mut Vec<CommitInfo>) {
+        self.filter_unbounded(commits);
+        self.truncate(commits);
+    }
+
+    /// Same as `filter` but keeps commits beyond the count, to extend the range with older ones.
+    pub fn filter_unbounded(&self, commits: &mut Vec<CommitInfo>) {
+        if self.first_parent {
+            *commits = commit::first_parent_history(std::mem::take(commits));
+        }
+        if self.base.is_none() {
+            commits.retain(|c| self.is_in_window(c));
+        }
+    }
+
+    /// Drop commits beyond the count of this range.
+    fn truncate(&self, commits: &mut Vec<CommitInfo>) {
+        if self.is_count_bounded() {
+            commits.truncate(self.count as usize);
+        }
+    }
+
+    /// Returns the number of commits having metadata to find before sampling them. A count-bounded
+    /// range doesn't need more than its count even if it has more samples.
+    pub fn available_target(&self) -> usize {
+        if self.is_count_bounded() {
+            self.count.min(self.samples) as usize
+        } else {
+            self.samples as usize
+        }
+    }
+
+    /// Sample commits having metadata, which may have been found beyond the count of this range.
+    pub fn sample_available(&self, mut available: Vec<CommitInfo>) -> Vec<CommitInfo> {
+        self.truncate(&mut available);
+        self.sample(available)
+    }
+
     pub fn sample(&self, candidates: Vec<CommitInfo>) -> Vec<CommitInfo> {
-        let sample_index = (self.count / self.samples).max(1);
+        if self.sampling != Sampling::Stride {
+            // Aggregating strategies need values of all commits, so they're sampled later
+            // once values are obtained.
+            return candidates;
+        }
+
+        let sample_index = (candidates.len() as u32 / self.samples).max(1);
         debug!(
             "Sampleing {} from {} with sample_index={}",
             self.samples,
@@ -41,7 +176,185 @@ impl Range {
         samples
     }
 
+    /// Override bounds of this range by those given in the spec, keeping the others.
+    pub fn apply(&mut self, spec: RangeSpec) {
+        if let Some(count) = spec.count {
+            self.count = count.max(1);
+        }
+        if spec.since.is_some() {
+            self.since = spec.since;
+        }
+        if spec.until.is_some() {
+            self.until = spec.until;
+        }
+        if spec.base.is_some() {
+            self.base = spec.base;
+        }
+        if spec.head.is_some() {
+            self.from = spec.head;
+        }
+    }
+
+    /// Returns true if the range can be zoomed, which a range between refs can't be as it has
+    /// all commits between them.
+    pub fn can_zoom(&self) -> bool {
+        self.base.is_none()
+    }
+
+    /// Shrink or expand the range by the factor, either its date window or its count.
     pub fn zoom(&mut self, factor: f64) {
+        if let Some(since) = self.since {
+            // Shrink or expand the window while keeping its end fixed.
+            let until = self.until.unwrap_or_else(Utc::now);
+            let width = (until - since).num_seconds() as f64 * factor;
+            self.since = Some(until - chrono::Duration::seconds(width.max(1.0) as i64));
+            return;
+        }
         self.count = ((self.count as f64 * factor) as u32).max(self.samples);
     }
 }
+
+/// Parse the value of `<input type="date">` into a timestamp at the beginning or the end of the
+/// day in local timezone.
+pub fn parse_date_input(value: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(value, DATE_INPUT_FORMAT).ok()?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Format a timestamp into the value format of `<input type="date">`.
+pub fn format_date_input(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| {
+        t.with_timezone(&Local)
+            .format(DATE_INPUT_FORMAT)
+            .to_string()
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(value: serde_json::Value) -> Result<RangeSpec, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_empty_spec() {
+        assert_eq!(spec(json!({})).unwrap(), RangeSpec::default());
+        let nulls = json!({"count": null, "since": null, "until": null, "base": null});
+        assert_eq!(spec(nulls).unwrap(), RangeSpec::default());
+    }
+
+    #[test]
+    fn test_spec() {
+        let parsed = spec(json!({
+            "count": 50,
+            "since": "2020-06-01T00:00:00Z",
+            "until": "2020-06-30T12:00:00+09:00",
+            "base": "v1.0.0",
+            "head": "master",
+        }))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            RangeSpec {
+                count: Some(50),
+                since: Some(utc("2020-06-01T00:00:00Z")),
+                until: Some(utc("2020-06-30T03:00:00Z")),
+                base: Some("v1.0.0".to_string()),
+                head: Some("master".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_spec_of_dates() {
+        let parsed = spec(json!({"since": "2020-06-01", "until": "2020-06-30"})).unwrap();
+        assert_eq!(parsed.since, parse_date_input("2020-06-01", false));
+        assert_eq!(parsed.until, parse_date_input("2020-06-30", true));
+        assert!(parsed.since.unwrap() < parsed.until.unwrap());
+        let day = spec(json!({"since": "2020-06-01", "until": "2020-06-01"})).unwrap();
+        assert!(day.since.unwrap() < day.until.unwrap());
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        for value in &[
+            json!({"since": "yesterday"}),
+            json!({"until": "2020-13-01"}),
+            json!({"since": ""}),
+            json!({"count": -1}),
+            json!({"counts": 10}),
+        ] {
+            assert!(spec(value.clone()).is_err(), "{} is accepted", value);
+        }
+    }
+
+    #[test]
+    fn test_zoom_window() {
+        let mut range = Range::new(None, 100, 10);
+        range.since = Some(utc("2020-06-01T00:00:00Z"));
+        range.until = Some(utc("2020-06-09T00:00:00Z"));
+        range.zoom(0.5);
+        assert_eq!(range.since, Some(utc("2020-06-05T00:00:00Z")));
+        assert_eq!(range.until, Some(utc("2020-06-09T00:00:00Z")));
+        assert_eq!(range.count, 100);
+    }
+
+    #[test]
+    fn test_zoom_until_only() {
+        let mut range = Range::new(None, 100, 10);
+        range.until = Some(utc("2020-06-09T00:00:00Z"));
+        assert!(!range.is_windowed());
+        range.zoom(0.5);
+        assert_eq!(range.count, 50);
+        range.zoom(0.1);
+        assert_eq!(range.count, 10);
+        range.zoom(2.0);
+        assert_eq!(range.count, 20);
+        assert_eq!(range.since, None);
+        assert_eq!(range.until, Some(utc("2020-06-09T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_can_zoom() {
+        let mut range = Range::new(None, 100, 10);
+        assert!(range.can_zoom());
+        range.since = Some(utc("2020-06-01T00:00:00Z"));
+        assert!(range.can_zoom());
+        range.base = Some("v1.0.0".to_string());
+        assert!(!range.can_zoom());
+    }
+
+    #[test]
+    fn test_apply_keeps_unset_bounds() {
+        let mut range = Range::new(Some("master".to_string()), 100, 10);
+        range.base = Some("v1.0.0".to_string());
+        range.apply(RangeSpec {
+            count: Some(0),
+            since: Some(utc("2020-06-01T00:00:00Z")),
+            ..RangeSpec::default()
+        });
+        assert_eq!(range.count, 1);
+        assert_eq!(range.since, Some(utc("2020-06-01T00:00:00Z")));
+        assert_eq!(range.until, None);
+        assert_eq!(range.base.as_deref(), Some("v1.0.0"));
+        assert_eq!(range.from.as_deref(), Some("master"));
+    }
+}
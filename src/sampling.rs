@@ -0,0 +1,382 @@
+use crate::commit::{self, CommitInfo};
+use crate::component::{CommitSpan, CommitViewData};
+use crate::config::Sampling;
+use std::collections::HashMap;
+
+/// Reduce datapoints of the view data down to at most `samples` by the given strategy.
+/// Stride sampling is applied on commits listing before fetching metadata, so it's no-op here.
+pub fn downsample(data: CommitViewData, sampling: Sampling, samples: usize) -> CommitViewData {
+    let samples = samples.max(1);
+    match sampling {
+        Sampling::Stride => data,
+        Sampling::Mean => aggregate_buckets(data, samples, |values| {
+            let sum: f64 = values.iter().map(|(_, v)| v).sum();
+            (0, sum / values.len() as f64)
+        }),
+        Sampling::Min => aggregate_buckets(data, samples, |values| {
+            pick_extreme(values, |candidate, current| candidate < current)
+        }),
+        Sampling::Max => aggregate_buckets(data, samples, |values| {
+            pick_extreme(values, |candidate, current| candidate > current)
+        }),
+        Sampling::Lttb => largest_triangle_three_buckets(data, samples),
+    }
+}
+
+fn pick_extreme<F>(values: &[(usize, f64)], prefer: F) -> (usize, f64)
+where
+    F: Fn(f64, f64) -> bool,
+{
+    let mut extreme = values[0];
+    for &(i, v) in &values[1..] {
+        if prefer(v, extreme.1) {
+            extreme = (i, v);
+        }
+    }
+    extreme
+}
+
+/// Split commits into consecutive buckets and aggregate values in each bucket into a datapoint.
+/// The aggregator receives pairs of (index in bucket, value) for commits having value and returns
+/// index of the commit to represent the bucket with its aggregated value.
+fn aggregate_buckets<F>(data: CommitViewData, samples: usize, aggregate: F) -> CommitViewData
+where
+    F: Fn(&[(usize, f64)]) -> (usize, f64),
+{
+    let CommitViewData {
+        commits,
+        metadata,
+        series,
+        mut tags,
+        statuses,
+        ..
+    } = data;
+    if commits.is_empty() {
+        return CommitViewData {
+            commits,
+            metadata,
+            series,
+            spans: HashMap::new(),
+            tags,
+            statuses,
+        };
+    }
+
+    let bucket_size = commits.len().div_ceil(samples);
+    let mut sampled = Vec::with_capacity(samples);
+    let mut values = HashMap::with_capacity(samples);
+    let mut series_values = vec![HashMap::with_capacity(samples); series.len()];
+    let mut spans = HashMap::with_capacity(samples);
+    let mut bucket_tags = HashMap::new();
+    for bucket in commits.chunks(bucket_size) {
+        let bucket_values = values_in_bucket(bucket, &metadata);
+        let representative = if bucket_values.is_empty() {
+            bucket[0].clone()
+        } else {
+            let (i, value) = aggregate(&bucket_values);
+            values.insert(bucket[i].sha.clone(), value);
+            bucket[i].clone()
+        };
+        // Extra series are aggregated in the same way but independently, and placed at the
+        // representative commit of the main value.
+        for (source, sampled_values) in series.iter().zip(&mut series_values) {
+            let bucket_values = values_in_bucket(bucket, source);
+            if !bucket_values.is_empty() {
+                sampled_values.insert(representative.sha.clone(), aggregate(&bucket_values).1);
+            }
+        }
+        let names: Vec<_> = bucket
+            .iter()
+            .rev()
+            .filter_map(|c| tags.remove(&c.sha))
+            .flatten()
+            .collect();
+        if !names.is_empty() {
+            bucket_tags.insert(representative.sha.clone(), names);
+        }
+        if bucket.len() > 1 {
+            spans.insert(
+                representative.sha.clone(),
+                CommitSpan {
+                    newest: bucket[0].clone(),
+                    oldest: bucket[bucket.len() - 1].clone(),
+                    count: bucket.len(),
+                },
+            );
+        }
+        sampled.push(representative);
+    }
+
+    CommitViewData {
+        commits: sampled,
+        metadata: values,
+        series: series_values,
+        spans,
+        tags: bucket_tags,
+        statuses,
+    }
+}
+
+fn values_in_bucket(bucket: &[CommitInfo], values: &HashMap<String, f64>) -> Vec<(usize, f64)> {
+    bucket
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| values.get(&c.sha).map(|v| (i, *v)))
+        .collect()
+}
+
+/// Select commits by Largest-Triangle-Three-Buckets algorithm, which keeps the visual shape of
+/// the line including spikes. Commits without value are dropped.
+fn largest_triangle_three_buckets(data: CommitViewData, samples: usize) -> CommitViewData {
+    let CommitViewData {
+        commits,
+        metadata,
+        series,
+        tags,
+        statuses,
+        ..
+    } = data;
+    let points: Vec<_> = commits
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| metadata.get(&c.sha).map(|v| (i, *v)))
+        .collect();
+
+    let selected: Vec<usize> = if points.len() <= samples {
+        points.iter().map(|(i, _)| *i).collect()
+    } else if samples < 3 {
+        // Too few samples to pick any between the first and the last.
+        let ends = [points[0].0, points[points.len() - 1].0];
+        ends[..samples].to_vec()
+    } else {
+        let n = points.len();
+        let every = (n - 2) as f64 / (samples - 2) as f64;
+        let mut selected = Vec::with_capacity(samples);
+        selected.push(points[0].0);
+        let mut a = 0;
+        for i in 0..samples - 2 {
+            let avg_start = ((i + 1) as f64 * every) as usize + 1;
+            let avg_end = (((i + 2) as f64 * every) as usize + 1).min(n);
+            let avg_len = (avg_end - avg_start) as f64;
+            let avg_x = points[avg_start..avg_end]
+                .iter()
+                .map(|(x, _)| *x as f64)
+                .sum::<f64>()
+                / avg_len;
+            let avg_y = points[avg_start..avg_end]
+                .iter()
+                .map(|(_, y)| y)
+                .sum::<f64>()
+                / avg_len;
+
+            let (ax, ay) = (points[a].0 as f64, points[a].1);
+            let range_start = (i as f64 * every) as usize + 1;
+            let range_end = ((i + 1) as f64 * every) as usize + 1;
+            let mut max_area = -1.0;
+            let mut max_index = range_start;
+            for (j, &(x, y)) in points.iter().enumerate().take(range_end).skip(range_start) {
+                let area = ((ax - avg_x) * (y - ay) - (ax - x as f64) * (avg_y - ay)).abs();
+                if area > max_area {
+                    max_area = area;
+                    max_index = j;
+                }
+            }
+            selected.push(points[max_index].0);
+            a = max_index;
+        }
+        selected.push(points[n - 1].0);
+        selected
+    };
+
+    let mut sampled = Vec::with_capacity(selected.len());
+    let mut values = HashMap::with_capacity(selected.len());
+    for i in selected {
+        let commit = commits[i].clone();
+        if let Some(v) = metadata.get(&commit.sha) {
+            values.insert(commit.sha.clone(), *v);
+        }
+        sampled.push(commit);
+    }
+    // Extra series keep their values at commits selected by the main value.
+    let series = series
+        .into_iter()
+        .map(|mut values| {
+            values.retain(|sha, _| sampled.iter().any(|c| &c.sha == sha));
+            values
+        })
+        .collect();
+    let listed: Vec<_> = commits.into_iter().map(|c| c.sha).collect();
+    let tags = commit::carry_tags(&listed, &sampled, tags);
+    CommitViewData {
+        commits: sampled,
+        metadata: values,
+        series,
+        spans: HashMap::new(),
+        tags,
+        statuses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::{CommitInfo, UserInfo};
+    use std::time::SystemTime;
+
+    fn commit(sha: &str) -> CommitInfo {
+        let user = UserInfo {
+            name: "user".to_string(),
+            email: "user@example.com".to_string(),
+        };
+        CommitInfo {
+            sha: sha.to_string(),
+            author: user.clone(),
+            author_date: SystemTime::UNIX_EPOCH,
+            committer: user,
+            commit_date: SystemTime::UNIX_EPOCH,
+            message: String::new(),
+            view_url: String::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Make view data of commits named by index, newest first, with the given values.
+    fn view_data(values: &[Option<f64>]) -> CommitViewData {
+        let commits: Vec<_> = (0..values.len()).map(|i| commit(&i.to_string())).collect();
+        let metadata = commits
+            .iter()
+            .zip(values)
+            .filter_map(|(c, v)| v.map(|v| (c.sha.clone(), v)))
+            .collect();
+        CommitViewData {
+            commits,
+            metadata,
+            series: Vec::new(),
+            spans: HashMap::new(),
+            tags: HashMap::new(),
+            statuses: HashMap::new(),
+        }
+    }
+
+    fn shas(data: &CommitViewData) -> Vec<&str> {
+        data.commits.iter().map(|c| c.sha.as_str()).collect()
+    }
+
+    #[test]
+    fn test_stride_keeps_data() {
+        let data = view_data(&[Some(1.0), None, Some(3.0)]);
+        assert_eq!(downsample(data.clone(), Sampling::Stride, 1), data);
+    }
+
+    #[test]
+    fn test_buckets_of_empty_data() {
+        for sampling in &[Sampling::Mean, Sampling::Min, Sampling::Max] {
+            let sampled = downsample(view_data(&[]), *sampling, 10);
+            assert!(sampled.commits.is_empty());
+            assert!(sampled.metadata.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_buckets_with_enough_samples() {
+        let data = view_data(&[Some(1.0), Some(2.0), Some(3.0)]);
+        for samples in &[3, 10] {
+            let sampled = downsample(data.clone(), Sampling::Mean, *samples);
+            assert_eq!(shas(&sampled), vec!["0", "1", "2"]);
+            assert_eq!(sampled.metadata, data.metadata);
+            assert!(sampled.spans.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_mean_buckets() {
+        let data = view_data(&[Some(1.0), Some(2.0), Some(3.0), None, Some(5.0)]);
+        let sampled = downsample(data, Sampling::Mean, 2);
+        assert_eq!(shas(&sampled), vec!["0", "3"]);
+        assert_eq!(sampled.metadata.get("0"), Some(&2.0));
+        assert_eq!(sampled.metadata.get("3"), Some(&5.0));
+        let span = &sampled.spans["0"];
+        assert_eq!(
+            (span.newest.sha.as_str(), span.oldest.sha.as_str()),
+            ("0", "2")
+        );
+        assert_eq!(span.count, 3);
+        assert_eq!(sampled.spans["3"].count, 2);
+    }
+
+    #[test]
+    fn test_min_max_buckets() {
+        let data = view_data(&[Some(2.0), Some(1.0), Some(3.0), Some(4.0)]);
+        let min = downsample(data.clone(), Sampling::Min, 2);
+        assert_eq!(shas(&min), vec!["1", "2"]);
+        assert_eq!(min.metadata.get("1"), Some(&1.0));
+        assert_eq!(min.metadata.get("2"), Some(&3.0));
+        let max = downsample(data, Sampling::Max, 2);
+        assert_eq!(shas(&max), vec!["0", "3"]);
+        assert_eq!(max.metadata.get("0"), Some(&2.0));
+        assert_eq!(max.metadata.get("3"), Some(&4.0));
+    }
+
+    #[test]
+    fn test_buckets_without_values() {
+        let sampled = downsample(view_data(&[None, None, None, None]), Sampling::Max, 2);
+        assert_eq!(shas(&sampled), vec!["0", "2"]);
+        assert!(sampled.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_buckets_with_zero_samples() {
+        let sampled = downsample(view_data(&[Some(1.0), Some(3.0)]), Sampling::Mean, 0);
+        assert_eq!(shas(&sampled), vec!["0"]);
+        assert_eq!(sampled.metadata.get("0"), Some(&2.0));
+    }
+
+    #[test]
+    fn test_buckets_carry_tags() {
+        let mut data = view_data(&[Some(1.0), Some(2.0)]);
+        data.tags.insert("1".to_string(), vec!["v1".to_string()]);
+        let sampled = downsample(data, Sampling::Mean, 1);
+        assert_eq!(sampled.tags.get("0"), Some(&vec!["v1".to_string()]));
+    }
+
+    #[test]
+    fn test_lttb_of_empty_data() {
+        let sampled = downsample(view_data(&[]), Sampling::Lttb, 3);
+        assert!(sampled.commits.is_empty());
+        let sampled = downsample(view_data(&[None, None]), Sampling::Lttb, 3);
+        assert!(sampled.commits.is_empty());
+    }
+
+    #[test]
+    fn test_lttb_with_enough_samples() {
+        let data = view_data(&[Some(1.0), None, Some(3.0)]);
+        let sampled = downsample(data, Sampling::Lttb, 2);
+        assert_eq!(shas(&sampled), vec!["0", "2"]);
+    }
+
+    #[test]
+    fn test_lttb_with_less_than_three_samples() {
+        let data = view_data(&[Some(1.0), Some(5.0), Some(2.0), Some(3.0)]);
+        let sampled = downsample(data.clone(), Sampling::Lttb, 2);
+        assert_eq!(shas(&sampled), vec!["0", "3"]);
+        let sampled = downsample(data, Sampling::Lttb, 0);
+        assert_eq!(shas(&sampled), vec!["0"]);
+    }
+
+    #[test]
+    fn test_lttb_keeps_ends_and_spike() {
+        let mut values = vec![Some(0.0); 9];
+        values[4] = Some(10.0);
+        let sampled = downsample(view_data(&values), Sampling::Lttb, 3);
+        assert_eq!(shas(&sampled), vec!["0", "4", "8"]);
+        assert_eq!(sampled.metadata.get("4"), Some(&10.0));
+    }
+
+    #[test]
+    fn test_lttb_with_same_values() {
+        let sampled = downsample(view_data(&[Some(1.0); 10]), Sampling::Lttb, 4);
+        assert_eq!(sampled.commits.len(), 4);
+        assert_eq!(sampled.commits[0].sha, "0");
+        assert_eq!(sampled.commits[3].sha, "9");
+    }
+}
@@ -0,0 +1,208 @@
+use crate::api::github::GitHubApi;
+use crate::api::static_metadata::StaticMetadataApi;
+use crate::api::{
+    self, CommitCompareRequest, CommitListRequest, CommitMetadataRequest, ComparePage, Manifest,
+    ManifestRequest, Tag, TagListRequest, TagsApi,
+};
+use crate::cache::ApiCache;
+use crate::commit::CommitInfo;
+use crate::config::{Config, TagSource};
+use crate::dataset::{Apis, CommitDataSet, Stage};
+use crate::range::Range;
+use jmespatch::Rcvar;
+use log::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use yew::Callback;
+
+type CachedCommitsApi = ApiCache<CommitListRequest, Vec<CommitInfo>, GitHubApi>;
+type CachedCompareApi = ApiCache<CommitCompareRequest, ComparePage, GitHubApi>;
+type CachedMetadataApi = ApiCache<CommitMetadataRequest, Rcvar, StaticMetadataApi>;
+type CachedManifestApi = ApiCache<ManifestRequest, Manifest, StaticMetadataApi>;
+type CachedTagsApi = ApiCache<TagListRequest, Vec<Tag>, TagsApi>;
+
+/// Data store shared by all charts in the dashboard. Commits are listed once per range, and
+/// metadata of each file are fetched and parsed once no matter how many charts query them.
+pub struct DataStore {
+    repo: String,
+    apis: Apis<
+        CachedCommitsApi,
+        CachedCompareApi,
+        CachedMetadataApi,
+        CachedManifestApi,
+        CachedTagsApi,
+    >,
+    /// Incremented on every fetch so that datasets delivered for outdated ranges are discarded.
+    generation: u64,
+    /// Latest dataset of each file.
+    datasets: HashMap<String, Rc<CommitDataSet>>,
+    /// Tags on each commit, which are listed concurrently with metadata and attached to datasets
+    /// once listed.
+    tags: Option<HashMap<String, Vec<String>>>,
+    /// Failure of listing commits, which fails datasets of all files.
+    listing_failure: Option<Failure>,
+    /// Failure of fetching metadata of each file.
+    failures: HashMap<String, Failure>,
+}
+
+/// Failure of the latest fetch, to be shown in place of charts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub stage: Stage,
+    pub message: String,
+}
+
+/// Events delivered to the owner of the store, to be passed back to `DataStore::receive`.
+#[derive(Debug)]
+pub enum Event {
+    /// Dataset of the file, which might be partial
+    DataReady(u64, String, CommitDataSet),
+    /// Tags on each commit
+    TagsReady(u64, HashMap<String, Vec<String>>),
+    /// Error fetching data of the file, or of all files if none
+    FetchError(u64, Option<String>, api::Error),
+}
+
+impl DataStore {
+    pub fn new(config: &Config) -> Self {
+        let tags_api = match config.tags {
+            Some(TagSource::GitHub) => TagsApi::GitHub(GitHubApi::new()),
+            Some(TagSource::Static) => {
+                TagsApi::Static(StaticMetadataApi::new(config.data_url.clone()))
+            }
+            None => TagsApi::Disabled,
+        };
+        let apis = Apis {
+            commits: Rc::new(RefCell::new(ApiCache::new(GitHubApi::new()))),
+            compare: Rc::new(RefCell::new(ApiCache::new(GitHubApi::new()))),
+            metadata: Rc::new(RefCell::new(ApiCache::new(StaticMetadataApi::new(
+                config.data_url.clone(),
+            )))),
+            manifest: Rc::new(RefCell::new(ApiCache::new(StaticMetadataApi::new(
+                config.data_url.clone(),
+            )))),
+            tags: Rc::new(RefCell::new(ApiCache::new(tags_api))),
+        };
+
+        Self {
+            repo: config.repo.clone(),
+            apis,
+            generation: 0,
+            datasets: HashMap::new(),
+            tags: None,
+            listing_failure: None,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Drop everything including cached API responses to start over with the config. The
+    /// generation keeps increasing so that responses to the requests made so far are discarded.
+    pub fn reset(&mut self, config: &Config) {
+        let generation = self.generation;
+        *self = Self::new(config);
+        self.generation = generation;
+    }
+
+    /// Start fetching datasets of the files in the range. Datasets of the previous range are
+    /// kept until those of the new range arrive, while failures are cleared to retry.
+    pub fn fetch<I>(&mut self, range: &Range, files: I, callback: Callback<Event>)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.generation += 1;
+        self.listing_failure = None;
+        self.failures.clear();
+        let generation = self.generation;
+        let mut files: Vec<_> = files.into_iter().collect();
+        files.sort();
+        files.dedup();
+
+        let callback_cp = callback.clone();
+        CommitDataSet::list_tags(&self.apis, &self.repo, move |tags| {
+            callback_cp.emit(Event::TagsReady(generation, tags));
+        });
+
+        let apis = self.apis.clone();
+        let range_cp = range.clone();
+        CommitDataSet::list_range(self.apis.clone(), &self.repo, range.clone(), move |resp| {
+            let list = match resp {
+                Ok(list) => list,
+                Err(e) => {
+                    callback.emit(Event::FetchError(generation, None, e));
+                    return;
+                }
+            };
+            for file in files {
+                let callback = callback.clone();
+                let file_cp = file.clone();
+                CommitDataSet::collect_metadata(
+                    &apis,
+                    file,
+                    range_cp.clone(),
+                    &list,
+                    move |resp| {
+                        callback.emit(match resp {
+                            Ok(ds) => Event::DataReady(generation, file_cp.clone(), ds),
+                            Err(e) => Event::FetchError(generation, Some(file_cp.clone()), e),
+                        })
+                    },
+                );
+            }
+        });
+    }
+
+    /// Apply the event to the store, returning true if any dataset has been updated.
+    pub fn receive(&mut self, event: Event) -> bool {
+        match event {
+            Event::DataReady(generation, file, dataset) if generation == self.generation => {
+                let dataset = match &self.tags {
+                    Some(tags) => dataset.with_tags(tags),
+                    None => dataset,
+                };
+                self.datasets.insert(file, Rc::new(dataset));
+                true
+            }
+            Event::TagsReady(generation, tags) if generation == self.generation => {
+                for dataset in self.datasets.values_mut() {
+                    *dataset = Rc::new(dataset.with_tags(&tags));
+                }
+                self.tags = Some(tags);
+                true
+            }
+            Event::FetchError(generation, file, e) if generation == self.generation => {
+                error!("Error in fetching data: {}", e);
+                let message = e.to_string();
+                match file {
+                    Some(file) => {
+                        let stage = Stage::MetadataFetch;
+                        self.failures.insert(file, Failure { stage, message });
+                    }
+                    None => {
+                        let stage = Stage::CommitListing;
+                        self.listing_failure = Some(Failure { stage, message });
+                    }
+                }
+                true
+            }
+            Event::DataReady(generation, ..)
+            | Event::TagsReady(generation, _)
+            | Event::FetchError(generation, ..) => {
+                debug!("Discarding data of outdated generation {}", generation);
+                false
+            }
+        }
+    }
+
+    pub fn dataset(&self, file: &str) -> Option<Rc<CommitDataSet>> {
+        self.datasets.get(file).map(Rc::clone)
+    }
+
+    /// Returns the failure of the latest fetch for the file, if any.
+    pub fn failure(&self, file: &str) -> Option<Failure> {
+        self.listing_failure
+            .as_ref()
+            .or_else(|| self.failures.get(file))
+            .cloned()
+    }
+}